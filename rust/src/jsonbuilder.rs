@@ -455,6 +455,27 @@ impl JsonBuilder {
         Ok(self)
     }
 
+    /// Set a key and a signed integer type on an object.
+    pub fn set_int(&mut self, key: &str, val: i64) -> Result<&mut Self, JsonError> {
+        match self.current_state() {
+            State::ObjectNth => {
+                self.buf.push(',');
+            }
+            State::ObjectFirst => {
+                self.set_state(State::ObjectNth);
+            }
+            _ => {
+                debug_validate_fail!("invalid state");
+                return Err(JsonError::InvalidState);
+            }
+        }
+        self.buf.push('"');
+        self.buf.push_str(key);
+        self.buf.push_str("\":");
+        self.buf.push_str(&val.to_string());
+        Ok(self)
+    }
+
     pub fn set_float(&mut self, key: &str, val: f64) -> Result<&mut Self, JsonError> {
         match self.current_state() {
             State::ObjectNth => {
@@ -725,6 +746,14 @@ pub unsafe extern "C" fn jb_set_uint(js: &mut JsonBuilder, key: *const c_char, v
     return false;
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn jb_set_int(js: &mut JsonBuilder, key: *const c_char, val: i64) -> bool {
+    if let Ok(key) = CStr::from_ptr(key).to_str() {
+        return js.set_int(key, val).is_ok();
+    }
+    return false;
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn jb_set_float(js: &mut JsonBuilder, key: *const c_char, val: f64) -> bool {
     if let Ok(key) = CStr::from_ptr(key).to_str() {
@@ -788,6 +817,15 @@ mod test {
         assert_eq!(jb.buf, r#"{"first":false,"second":true"#);
     }
 
+    #[test]
+    fn test_set_int() {
+        let mut jb = JsonBuilder::new_object();
+        jb.set_int("first", -1).unwrap();
+        assert_eq!(jb.buf, r#"{"first":-1"#);
+        jb.set_int("second", 2).unwrap();
+        assert_eq!(jb.buf, r#"{"first":-1,"second":2"#);
+    }
+
     #[test]
     fn test_object_in_object() -> Result<(), JsonError> {
         let mut js = JsonBuilder::new_object();
@@ -17,7 +17,9 @@
 
 // written by Pierre Chifflier  <chifflier@wzdftpd.net>
 
-use crate::snmp::snmp::SNMPTransaction;
+use crate::snmp::snmp::{SNMPTransaction,SnmpValue,snmp_error_status_from_name};
+use crate::snmp::snmp_parser::PduType;
+use std::ffi::CStr;
 
 #[no_mangle]
 pub unsafe extern "C" fn rs_snmp_tx_get_version(tx: &mut SNMPTransaction,
@@ -27,30 +29,375 @@ pub unsafe extern "C" fn rs_snmp_tx_get_version(tx: &mut SNMPTransaction,
     *version = tx.version as u32;
 }
 
+/// Get the direction of the message that created the transaction
+/// (STREAM_TOSERVER or STREAM_TOCLIENT).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_direction(tx: &mut SNMPTransaction) -> u8
+{
+    tx.direction
+}
+
+/// Get the community string. Returns false if not present (SNMPv3).
 #[no_mangle]
 pub unsafe extern "C" fn rs_snmp_tx_get_community(tx: &mut SNMPTransaction,
                                            buf: *mut *const u8,
-                                           len: *mut u32)
+                                           len: *mut u32) -> bool
 {
     match tx.community {
         Some(ref c) => {
             *buf = c.as_ptr();
             *len = c.len() as u32;
+            true
+        },
+        None        => false
+    }
+}
+
+/// Get the authoritative engine ID (raw bytes). Returns false if the message
+/// does not use the USM security model.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_engine_id(tx: &mut SNMPTransaction,
+                                           buf: *mut *const u8,
+                                           len: *mut u32) -> bool
+{
+    match tx.engine_id {
+        Some(ref e) => {
+            *buf = e.as_ptr();
+            *len = e.len() as u32;
+            true
+        },
+        None        => false
+    }
+}
+
+/// Get the SNMPv3 message flags. Returns false for SNMPv1 and v2c.
+///
+/// Bits are defined in RFC 3412:
+/// - 0x1: authenticated (authNoPriv)
+/// - 0x2: encrypted (authPriv, only valid with 0x1)
+/// - 0x4: reportable
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_security_flags(tx: &mut SNMPTransaction,
+                                                flags: *mut u8) -> bool
+{
+    match tx.security_flags {
+        Some(f) => {
+            *flags = f.bits();
+            true
         },
-        None        => ()
+        None    => false
+    }
+}
+
+/// Get the generic trap code of a SNMPv1 trap.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_generic_trap(tx: &mut SNMPTransaction,
+                                              generic_trap: *mut u32) -> bool
+{
+    if let Some(ref info) = tx.info {
+        if let Some(ref trap) = info.trap_type {
+            *generic_trap = trap.generic_trap.0 as u32;
+            return true;
+        }
     }
+    false
 }
 
+/// Get the specific trap code of a SNMPv1 trap. Returns false unless the
+/// trap is enterprise specific (generic trap 6).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_specific_trap(tx: &mut SNMPTransaction,
+                                               specific_trap: *mut u32) -> bool
+{
+    if let Some(ref info) = tx.info {
+        if let Some(code) = info.trap_type.as_ref().and_then(|t| t.specific_code()) {
+            *specific_trap = code;
+            return true;
+        }
+    }
+    false
+}
+
+/// Get the PDU type of the request. Returns false if the PDU is not
+/// accessible (for ex. encrypted SNMPv3).
 #[no_mangle]
 pub unsafe extern "C" fn rs_snmp_tx_get_pdu_type(tx: &mut SNMPTransaction,
-                                          pdu_type: *mut u32)
+                                          pdu_type: *mut u32) -> bool
 {
     match tx.info {
         Some(ref info) => {
             *pdu_type = info.pdu_type.0 as u32;
+            true
+        },
+        None           => false
+    }
+}
+
+/// Get the PDU type of the response paired with the request, if any.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_response_pdu_type(tx: &mut SNMPTransaction,
+                                                   pdu_type: *mut u32) -> bool
+{
+    match tx.response_info {
+        Some(ref info) => {
+            *pdu_type = info.pdu_type.0 as u32;
+            true
+        },
+        None           => false
+    }
+}
+
+fn pdu_type_from_str(s: &str) -> Option<PduType> {
+    let t = match s.to_lowercase().as_str() {
+        "get_request"      => PduType::GetRequest,
+        "get_next_request" => PduType::GetNextRequest,
+        "response"         => PduType::Response,
+        "set_request"      => PduType::SetRequest,
+        "trap_v1"          => PduType::TrapV1,
+        "get_bulk_request" => PduType::GetBulkRequest,
+        "inform_request"   => PduType::InformRequest,
+        "trap_v2"          => PduType::TrapV2,
+        "report"           => PduType::Report,
+        _                  => return None,
+    };
+    Some(t)
+}
+
+/// Convert a PDU type name, as used in the EVE output, to its numeric value.
+/// Returns -1 if the name is unknown.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_cstr_pdu_type(s: *const std::os::raw::c_char)
+                                        -> std::os::raw::c_int
+{
+    let name: &CStr = CStr::from_ptr(s);
+    if let Ok(name) = name.to_str() {
+        if let Some(t) = pdu_type_from_str(name) {
+            return t.0 as std::os::raw::c_int;
+        }
+    }
+    -1
+}
+
+/// Get the message ID (msgID). Returns false if not present (SNMPv1 and v2c).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_msg_id(tx: &mut SNMPTransaction,
+                                        msg_id: *mut u32) -> bool
+{
+    match tx.msg_id {
+        Some(id) => {
+            *msg_id = id;
+            true
+        },
+        None     => false
+    }
+}
+
+/// Get the maximum message size supported by the sender (msgMaxSize).
+/// Returns false if not present (SNMPv1 and v2c).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_msg_max_size(tx: &mut SNMPTransaction,
+                                              max_size: *mut u32) -> bool
+{
+    match tx.msg_max_size {
+        Some(size) => {
+            *max_size = size;
+            true
+        },
+        None       => false
+    }
+}
+
+/// Get the security model of a SNMPv3 message (msgSecurityModel, for ex. 3
+/// for USM). Returns false for SNMPv1 and v2c messages, which do not have
+/// this field.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_security_model(tx: &mut SNMPTransaction,
+                                                model: *mut u32) -> bool
+{
+    match tx.security_model {
+        Some(m) => {
+            *model = m;
+            true
+        },
+        None    => false
+    }
+}
+
+/// Get the non-repeaters value of a GetBulkRequest. Returns false if the
+/// PDU is not a GetBulkRequest, or is not accessible.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_bulk_non_repeaters(tx: &mut SNMPTransaction,
+                                                    non_repeaters: *mut u32) -> bool
+{
+    match tx.info.as_ref().and_then(|info| info.bulk.as_ref()) {
+        Some(bulk) => {
+            *non_repeaters = bulk.non_repeaters;
+            true
         },
-        None           => {
-            *pdu_type = 0xffffffff;
+        None       => false
+    }
+}
+
+/// Get the max-repetitions value of a GetBulkRequest. Returns false if the
+/// PDU is not a GetBulkRequest, or is not accessible.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_bulk_max_repetitions(tx: &mut SNMPTransaction,
+                                                      max_repetitions: *mut u32) -> bool
+{
+    match tx.info.as_ref().and_then(|info| info.bulk.as_ref()) {
+        Some(bulk) => {
+            *max_repetitions = bulk.max_repetitions;
+            true
+        },
+        None       => false
+    }
+}
+
+/// Get the error-status of the transaction: the one of the paired response
+/// if any, else the one of the message itself if it is a response or a
+/// report. Returns false for requests without a response, and if the PDU is
+/// not accessible (for ex. encrypted SNMPv3).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_error_status(tx: &mut SNMPTransaction,
+                                              error: *mut i32) -> bool
+{
+    let info = match (&tx.response_info, &tx.info) {
+        (Some(info), _) => info,
+        (None, Some(info)) if info.pdu_type == PduType::Response ||
+            info.pdu_type == PduType::Report => info,
+        _ => return false,
+    };
+    *error = info.err.0 as i32;
+    true
+}
+
+/// Convert an error-status name (RFC 3416, for ex. noSuchName) to its
+/// numeric value. Returns -1 if the name is unknown.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_cstr_error_status(s: *const std::os::raw::c_char)
+                                            -> std::os::raw::c_int
+{
+    let name: &CStr = CStr::from_ptr(s);
+    if let Ok(name) = name.to_str() {
+        if let Some(code) = snmp_error_status_from_name(name) {
+            return code as std::os::raw::c_int;
         }
     }
+    -1
+}
+
+/// Get the number of varbinds of the transaction, request and response
+/// included. Returns false if no PDU is available (encrypted messages).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_vars_count(tx: &mut SNMPTransaction,
+                                            count: *mut u32) -> bool
+{
+    if tx.info.is_none() && tx.response_info.is_none() {
+        return false;
+    }
+    *count = tx.varbinds().count() as u32;
+    true
+}
+
+/// Get the OID of a varbind of the transaction, in dotted form (for ex.
+/// 1.3.6.1.2.1.1.1.0).
+///
+/// Varbinds of the request are indexed first, followed by those of the response.
+/// The string is built on the first call for a varbind, and the returned
+/// buffer is owned by the transaction: it remains valid as long as the
+/// transaction is not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_vars(tx: &mut SNMPTransaction,
+                                      index: u32,
+                                      buf: *mut *const u8,
+                                      len: *mut u32) -> bool
+{
+    match tx.oid_str(index as usize) {
+        Some(oid) => {
+            *buf = oid.as_ptr();
+            *len = oid.len() as u32;
+            true
+        },
+        None      => false
+    }
+}
+
+/// Get the symbolic name of the OID of a varbind (for ex. `sysDescr.0`), or
+/// its dotted form if OID names are not configured or the OID is unknown.
+///
+/// Varbinds are indexed as in `rs_snmp_tx_get_vars`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_var_name(tx: &mut SNMPTransaction,
+                                          index: u32,
+                                          buf: *mut *const u8,
+                                          len: *mut u32) -> bool
+{
+    match tx.oid_name(index as usize) {
+        Some(name) => {
+            *buf = name.as_ptr();
+            *len = name.len() as u32;
+            true
+        },
+        None       => false
+    }
+}
+
+/// Get a varbind of the transaction: the OID (DER encoded content) and its value.
+///
+/// Varbinds of the request are indexed first, followed by those of the response.
+/// The type of the value is returned as its BER tag: 0x02 (Integer),
+/// 0x04 (OctetString), 0x05 (Null), 0x06 (Oid), 0x40 (IpAddress), 0x41 (Counter32),
+/// 0x42 (Gauge32), 0x43 (TimeTicks), 0x46 (Counter64), or 0 for unknown values.
+/// OctetString and Oid values are returned in `buf`, other values in `num`
+/// (Integer sign-extended, IpAddress as a host order integer).
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_tx_get_varbind(tx: &mut SNMPTransaction,
+                                         index: u32,
+                                         oid: *mut *const u8,
+                                         oid_len: *mut u32,
+                                         value_type: *mut u8,
+                                         num: *mut u64,
+                                         buf: *mut *const u8,
+                                         len: *mut u32) -> bool
+{
+    let (var_oid, value) = match tx.varbinds().nth(index as usize) {
+        Some(var) => var,
+        None      => return false,
+    };
+    let oid_bytes = var_oid.bytes();
+    *oid = oid_bytes.as_ptr();
+    *oid_len = oid_bytes.len() as u32;
+    *num = 0;
+    *buf = std::ptr::null();
+    *len = 0;
+    *value_type = match *value {
+        SnmpValue::Integer(n)          => { *num = n as u64; 0x02 },
+        SnmpValue::OctetString(ref s)  => { *buf = s.as_ptr(); *len = s.len() as u32; 0x04 },
+        SnmpValue::Null                => 0x05,
+        SnmpValue::Oid(ref o)          => {
+            let b = o.bytes();
+            *buf = b.as_ptr();
+            *len = b.len() as u32;
+            0x06
+        },
+        SnmpValue::IpAddress(ip)       => { *num = u32::from(ip) as u64; 0x40 },
+        SnmpValue::Counter32(n)        => { *num = n as u64; 0x41 },
+        SnmpValue::Gauge32(n)          => { *num = n as u64; 0x42 },
+        SnmpValue::TimeTicks(n)        => { *num = n as u64; 0x43 },
+        SnmpValue::Counter64(n)        => { *num = n; 0x46 },
+        SnmpValue::Unknown             => 0,
+    };
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snmp_pdu_type_from_str() {
+        assert_eq!(Some(PduType::SetRequest), pdu_type_from_str("set_request"));
+        assert_eq!(Some(PduType::GetBulkRequest), pdu_type_from_str("GET_BULK_REQUEST"));
+        assert_eq!(None, pdu_type_from_str("getbulk"));
+    }
 }
@@ -18,9 +18,11 @@
 // written by Pierre Chifflier  <chifflier@wzdftpd.net>
 
 use crate::jsonbuilder::{JsonBuilder, JsonError};
-use crate::snmp::snmp::{SNMPState,SNMPTransaction};
-use crate::snmp::snmp_parser::{NetworkAddress,PduType};
+use crate::snmp::mib;
+use crate::snmp::snmp::{SNMPTransaction,SNMPPduInfo,SNMPSecurityFlags,SnmpValue,snmp_error_status_name};
+use crate::snmp::snmp_parser::{ErrorStatus,NetworkAddress,PduType};
 use std::borrow::Cow;
+use der_parser::oid::Oid;
 
 fn str_of_pdu_type(t:&PduType) -> Cow<str> {
     match t {
@@ -37,51 +39,199 @@ fn str_of_pdu_type(t:&PduType) -> Cow<str> {
     }
 }
 
-fn snmp_log_response(jsb: &mut JsonBuilder, state: &mut SNMPState, tx: &mut SNMPTransaction) -> Result<(), JsonError>
-{
-    jsb.set_uint("version", state.version as u64)?;
-    if tx.encrypted {
-        jsb.set_string("pdu_type", "encrypted")?;
+fn str_of_error_status(err: ErrorStatus) -> Cow<'static, str> {
+    match snmp_error_status_name(err.0) {
+        Some(name) => Cow::Borrowed(name),
+        None       => Cow::Owned(format!("Unknown({})", err.0)),
+    }
+}
+
+fn str_of_security_level(flags: SNMPSecurityFlags) -> &'static str {
+    if flags.contains(SNMPSecurityFlags::AUTH | SNMPSecurityFlags::PRIV) {
+        "authPriv"
+    } else if flags.contains(SNMPSecurityFlags::AUTH) {
+        "authNoPriv"
+    } else if flags.contains(SNMPSecurityFlags::PRIV) {
+        // privacy without authentication is not allowed (RFC 3412)
+        "invalid"
     } else {
-        match tx.info {
-            Some(ref info) => {
-                jsb.set_string("pdu_type", &str_of_pdu_type(&info.pdu_type))?;
-                if info.err.0 != 0 {
-                    jsb.set_string("error", &format!("{:?}", info.err))?;
-                }
-                match info.trap_type {
-                    Some((trap_type, ref oid, address)) => {
-                        jsb.set_string("trap_type", &format!("{:?}", trap_type))?;
-                        jsb.set_string("trap_oid", &oid.to_string())?;
-                        match address {
-                            NetworkAddress::IPv4(ip) => {jsb.set_string("trap_address", &ip.to_string())?;},
-                        }
-                    },
-                    _ => ()
-                }
-                if info.vars.len() > 0 {
-                    jsb.open_array("vars")?;
-                    for var in info.vars.iter() {
-                        jsb.append_string(&var.to_string())?;
-                    }
-                    jsb.close()?;
-                }
-            },
-            _ => ()
-        }
-        if let Some(community) = &tx.community {
-            jsb.set_string("community", community)?;
+        "noAuthNoPriv"
+    }
+}
+
+fn snmp_log_varbind(jsb: &mut JsonBuilder, oid: &Oid, value: &SnmpValue) -> Result<(), JsonError>
+{
+    jsb.start_object()?;
+    jsb.set_string("oid", &oid.to_string())?;
+    if let Some(name) = mib::snmp_oid_name(oid) {
+        jsb.set_string("name", &name)?;
+    }
+    match *value {
+        SnmpValue::Integer(n) => {
+            jsb.set_string("type", "integer")?;
+            jsb.set_int("value", n)?;
+        },
+        SnmpValue::OctetString(ref s) => {
+            jsb.set_string("type", "octet_string")?;
+            jsb.set_string_from_bytes("value", s)?;
+        },
+        SnmpValue::Oid(ref oid) => {
+            jsb.set_string("type", "oid")?;
+            jsb.set_string("value", &oid.to_string())?;
+        },
+        SnmpValue::IpAddress(ip) => {
+            jsb.set_string("type", "ip_address")?;
+            jsb.set_string("value", &ip.to_string())?;
+        },
+        SnmpValue::Counter32(n) => {
+            jsb.set_string("type", "counter32")?;
+            jsb.set_uint("value", n as u64)?;
+        },
+        SnmpValue::Gauge32(n) => {
+            jsb.set_string("type", "gauge32")?;
+            jsb.set_uint("value", n as u64)?;
+        },
+        SnmpValue::TimeTicks(n) => {
+            jsb.set_string("type", "timeticks")?;
+            jsb.set_uint("value", n as u64)?;
+        },
+        SnmpValue::Counter64(n) => {
+            jsb.set_string("type", "counter64")?;
+            jsb.set_uint("value", n)?;
+        },
+        SnmpValue::Null => {
+            jsb.set_string("type", "null")?;
+        },
+        SnmpValue::Unknown => {
+            jsb.set_string("type", "unknown")?;
+        },
+    }
+    jsb.close()?;
+    return Ok(());
+}
+
+fn snmp_log_pdu_info(jsb: &mut JsonBuilder, info: &SNMPPduInfo) -> Result<(), JsonError>
+{
+    jsb.set_string("pdu_type", &str_of_pdu_type(&info.pdu_type))?;
+    match info.trap_type {
+        Some(ref trap) => {
+            jsb.set_string("trap_type", &format!("{:?}", trap.generic_trap))?;
+            jsb.set_uint("generic_trap", trap.generic_trap.0 as u64)?;
+            if let Some(code) = trap.specific_code() {
+                jsb.set_uint("specific_trap", code as u64)?;
+            }
+            jsb.set_uint("trap_timestamp", trap.timestamp as u64)?;
+            jsb.set_string("trap_oid", &trap.enterprise.to_string())?;
+            match trap.agent_addr {
+                NetworkAddress::IPv4(ip) => {jsb.set_string("trap_address", &ip.to_string())?;},
+            }
+        },
+        _ => {
+            jsb.set_int("request_id", info.request_id as i64)?;
         }
-        if let Some(usm) = &tx.usm {
-            jsb.set_string("usm", usm)?;
+    }
+    if let Some(ref bulk) = info.bulk {
+        jsb.set_uint("non_repeaters", bulk.non_repeaters as u64)?;
+        jsb.set_uint("max_repetitions", bulk.max_repetitions as u64)?;
+    }
+    if info.err.0 != 0 {
+        jsb.set_string("error", &str_of_error_status(info.err))?;
+    }
+    let mut vars = info.varbinds().peekable();
+    if vars.peek().is_some() {
+        jsb.open_array("varbinds")?;
+        for (oid, value) in vars {
+            snmp_log_varbind(jsb, &oid, value)?;
         }
+        jsb.close()?;
+    }
+    return Ok(());
+}
+
+fn snmp_log_json(tx: &SNMPTransaction, jsb: &mut JsonBuilder) -> Result<(), JsonError>
+{
+    jsb.set_uint("version", tx.version as u64)?;
+    if let Some(community) = &tx.community {
+        jsb.set_string("community", community)?;
+    }
+    if let Some(usm) = &tx.usm {
+        jsb.set_string("usm", usm)?;
+    }
+    if let Some(ref engine_id) = tx.engine_id {
+        let hex : String = engine_id.iter().map(|b| format!("{:02x}", b)).collect();
+        jsb.set_string("engine_id", &hex)?;
+    }
+    if let Some(flags) = tx.security_flags {
+        jsb.set_string("security_level", str_of_security_level(flags))?;
+    }
+    if tx.decrypted {
+        jsb.set_bool("decrypted", true)?;
+    }
+    if tx.encrypted {
+        jsb.set_bool("encrypted", true)?;
+    } else if let Some(ref info) = tx.info {
+        snmp_log_pdu_info(jsb, info)?;
+    }
+    if let Some(ref info) = tx.response_info {
+        jsb.open_object("response")?;
+        snmp_log_pdu_info(jsb, info)?;
+        jsb.close()?;
+    } else if tx.response_encrypted {
+        jsb.open_object("response")?;
+        jsb.set_bool("encrypted", true)?;
+        jsb.close()?;
     }
 
     return Ok(());
 }
 
 #[no_mangle]
-pub extern "C" fn rs_snmp_log_json_response(jsb: &mut JsonBuilder, state: &mut SNMPState, tx: &mut SNMPTransaction) -> bool
+pub extern "C" fn rs_snmp_log_json(tx: &mut SNMPTransaction, jsb: &mut JsonBuilder) -> bool
 {
-    snmp_log_response(jsb, state, tx).is_ok()
+    snmp_log_json(tx, jsb).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{STREAM_TOSERVER,STREAM_TOCLIENT};
+    use crate::jsonbuilder::{jb_len,jb_ptr};
+    use crate::snmp::snmp::SNMPState;
+    use crate::snmp::test_data::*;
+
+    fn log_first_tx(buf: &[u8], direction: u8) -> String {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(buf, direction));
+        let tx = state.get_tx_by_id(0).unwrap();
+        let mut jb = JsonBuilder::new_object();
+        snmp_log_json(tx, &mut jb).unwrap();
+        jb.close().unwrap();
+        let out = unsafe { std::slice::from_raw_parts(jb_ptr(&mut jb), jb_len(&jb)) };
+        String::from_utf8(out.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_snmp_log_v2c() {
+        assert_eq!(log_first_tx(SNMPV2C_RESP_VALUES, STREAM_TOCLIENT),
+                   concat!(r#"{"version":2,"community":"public","pdu_type":"response","request_id":1,"#,
+                           r#""varbinds":[{"oid":"1.3.6.1.2.1.1.1.0","type":"octet_string","value":"Linux"},"#,
+                           r#"{"oid":"1.3.6.1.2.1.1.3.0","type":"timeticks","value":256}]}"#));
+    }
+
+    #[test]
+    fn test_snmp_log_v1_trap() {
+        let js = log_first_tx(SNMPV1_TRAP, STREAM_TOSERVER);
+        assert!(js.starts_with(r#"{"version":1,"community":"public","pdu_type":"trap_v1","#));
+        assert!(js.contains(concat!(r#""generic_trap":6,"specific_trap":17,"trap_timestamp":1000,"#,
+                                    r#""trap_oid":"1.3.6.1.4.1.8072.2.3.1","trap_address":"192.168.1.1","#)));
+        assert!(js.contains(r#"{"oid":"1.3.6.1.2.1.2.2.1.1.2","type":"integer","value":2}"#));
+        assert!(!js.contains("request_id"));
+    }
+
+    #[test]
+    fn test_snmp_log_v3_encrypted() {
+        assert_eq!(log_first_tx(SNMPV3_ENC_REQ, STREAM_TOSERVER),
+                   concat!(r#"{"version":3,"usm":"admin","engine_id":"80001f888059dc486145a26322","#,
+                           r#""security_level":"authPriv","encrypted":true}"#));
+    }
 }
@@ -0,0 +1,174 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+//! Symbolic names of OIDs, for logging.
+//!
+//! Names are loaded from a file listing one OID and its name per line,
+//! either as `1.3.6.1.2.1.1.1 sysDescr`, or as `"sysDescr" "1.3.6.1.2.1.1.1"`
+//! as produced by `snmptranslate -Tz`. MIBs are not compiled: the file is only
+//! a table, keyed by the DER encoding of the OIDs. Names are resolved when
+//! logging, not when parsing.
+
+use crate::conf::conf_get;
+use der_parser::oid::Oid;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+type OidNames = HashMap<Vec<u8>, String>;
+
+static mut SNMP_OID_NAMES: Option<OidNames> = None;
+
+/// DER encoding of the content of a dotted OID, or None if not valid
+fn oid_encode(s: &str) -> Option<Vec<u8>> {
+    let arcs = s.split('.').map(|a| a.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] >= 40) {
+        return None;
+    }
+    let first = arcs[0].checked_mul(40)?.checked_add(arcs[1])?;
+    let mut out = Vec::new();
+    for &arc in std::iter::once(&first).chain(arcs[2..].iter()) {
+        let mut sub = vec![(arc & 0x7f) as u8];
+        let mut v = arc >> 7;
+        while v > 0 {
+            sub.push(0x80 | (v & 0x7f) as u8);
+            v >>= 7;
+        }
+        out.extend(sub.iter().rev());
+    }
+    Some(out)
+}
+
+/// Parse a table of names, with the OID first or last on each line, and
+/// optionally quoted. Empty lines and lines starting with `#` are ignored.
+fn parse_oid_names<R: BufRead>(reader: R) -> OidNames {
+    let mut names = HashMap::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_)   => break,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields : Vec<&str> = line.split_whitespace()
+            .map(|f| f.trim_matches('"').trim_start_matches('.')).collect();
+        if fields.len() != 2 {
+            SCLogError!("Invalid line {} in SNMP OID names: {}", n + 1, line);
+            continue;
+        }
+        match (oid_encode(fields[0]), oid_encode(fields[1])) {
+            (Some(oid), None) => {
+                names.insert(oid, fields[1].to_string());
+            },
+            (None, Some(oid)) => {
+                names.insert(oid, fields[0].to_string());
+            },
+            _ => {
+                SCLogError!("Invalid line {} in SNMP OID names: {}", n + 1, line);
+            }
+        }
+    }
+    names
+}
+
+/// Find the name of the longest known prefix of an OID (DER encoded),
+/// followed by the remaining arcs (for ex. `sysDescr.0`).
+fn lookup(names: &OidNames, oid: &[u8]) -> Option<String> {
+    // subidentifiers end on a byte without the high bit set
+    let ends = oid.iter().enumerate().filter(|&(_, b)| b & 0x80 == 0).map(|(i, _)| i);
+    let ends : Vec<usize> = ends.collect();
+    for &end in ends.iter().rev() {
+        if let Some(name) = names.get(&oid[..=end]) {
+            let mut s = name.clone();
+            let mut arc : u64 = 0;
+            for &b in &oid[end + 1..] {
+                arc = (arc << 7) | (b & 0x7f) as u64;
+                if b & 0x80 == 0 {
+                    let _ = write!(s, ".{}", arc);
+                    arc = 0;
+                }
+            }
+            return Some(s);
+        }
+    }
+    None
+}
+
+/// Get the symbolic name of an OID, if names were loaded and a prefix of the
+/// OID is known
+pub fn snmp_oid_name(oid: &Oid) -> Option<String> {
+    match unsafe { SNMP_OID_NAMES.as_ref() } {
+        Some(names) => lookup(names, oid.bytes()),
+        None        => None,
+    }
+}
+
+/// Load the names from the file set in `app-layer.protocols.snmp.oid-names`
+pub unsafe fn snmp_mib_parse_config() {
+    SNMP_OID_NAMES = None;
+    let filename = match conf_get("app-layer.protocols.snmp.oid-names") {
+        Some(filename) => filename,
+        None           => return,
+    };
+    match File::open(filename) {
+        Ok(f) => {
+            let names = parse_oid_names(BufReader::new(f));
+            SCLogConfig!("Loaded {} SNMP OID names from {}", names.len(), filename);
+            SNMP_OID_NAMES = Some(names);
+        },
+        Err(e) => {
+            SCLogError!("Failed to open SNMP OID names {}: {}", filename, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid_encode() {
+        assert_eq!(Some(vec![0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01]),
+                   oid_encode("1.3.6.1.2.1.1.1"));
+        assert_eq!(Some(vec![0x2b, 0x06, 0x01, 0x04, 0x01, 0xbf, 0x08]),
+                   oid_encode("1.3.6.1.4.1.8072"));
+        assert_eq!(None, oid_encode("1"));
+        assert_eq!(None, oid_encode("1.40"));
+        assert_eq!(None, oid_encode("1.3.a"));
+    }
+
+    #[test]
+    fn test_oid_names() {
+        let table = "# comment\n\n.1.3.6.1.2.1.1 system\n1.3.6.1.2.1.1.1 sysDescr\ninvalid\n\
+                     \"netSnmp\"\t\t\"1.3.6.1.4.1.8072\"\n1.3.6.1 1.3.6.2\n";
+        let names = parse_oid_names(table.as_bytes());
+        assert_eq!(3, names.len());
+        let sys_descr = [0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+        assert_eq!(Some("sysDescr.0".to_string()), lookup(&names, &sys_descr));
+        let sys_uptime = [0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00];
+        assert_eq!(Some("system.3.0".to_string()), lookup(&names, &sys_uptime));
+        let agent = [0x2b, 0x06, 0x01, 0x04, 0x01, 0xbf, 0x08, 0x03, 0x81, 0x00];
+        assert_eq!(Some("netSnmp.3.128".to_string()), lookup(&names, &agent));
+        let mib2 = [0x2b, 0x06, 0x01, 0x02, 0x01];
+        assert_eq!(None, lookup(&names, &mib2));
+    }
+}
@@ -22,3 +22,8 @@ extern crate snmp_parser;
 pub mod snmp;
 pub mod log;
 pub mod detect;
+pub mod mib;
+#[cfg(feature = "snmp-decrypt")]
+pub mod usm;
+#[cfg(test)]
+pub mod test_data;
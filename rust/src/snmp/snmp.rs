@@ -18,13 +18,23 @@
 // written by Pierre Chifflier  <chifflier@wzdftpd.net>
 
 use crate::snmp::snmp_parser::*;
+use crate::snmp::mib;
+#[cfg(feature = "snmp-decrypt")]
+use crate::snmp::usm;
 use crate::core;
 use crate::core::{AppProto,Flow,ALPROTO_UNKNOWN,ALPROTO_FAILED,STREAM_TOSERVER,STREAM_TOCLIENT};
 use crate::applayer::{self, *};
+use crate::conf::conf_get;
 use std;
+use std::collections::VecDeque;
 use std::ffi::CString;
+use std::net::Ipv4Addr;
+use std::ops::Range;
+use std::borrow::Cow;
 
-use der_parser::ber::BerObjectContent;
+use der_parser::ber::{BerObject,BerObjectContent,parse_ber_integer};
+#[cfg(feature = "snmp-decrypt")]
+use der_parser::ber::parse_ber_octetstring;
 use der_parser::der::parse_der_sequence;
 use der_parser::oid::Oid;
 use nom;
@@ -36,35 +46,205 @@ pub enum SNMPEvent {
     MalformedData,
     UnknownSecurityModel,
     VersionMismatch,
+    TooManyTransactions,
+    ExcessiveBulkRepetitions,
+    TruncatedPdu,
+    UnauthenticatedV3InCommunityFlow,
+    Report,
 }
 
-pub struct SNMPState<'a> {
-    /// SNMP protocol version
-    pub version: u32,
+/// Default maximum number of live transactions per flow
+const SNMP_DEFAULT_MAX_TX: usize = 256;
+
+static mut SNMP_MAX_TX: usize = SNMP_DEFAULT_MAX_TX;
+
+/// Default threshold above which the max-repetitions of a GetBulkRequest
+/// is considered as suspicious (amplification)
+const SNMP_DEFAULT_MAX_BULK_REPETITIONS: u32 = 1000;
+
+static mut SNMP_MAX_BULK_REPETITIONS: u32 = SNMP_DEFAULT_MAX_BULK_REPETITIONS;
+
+/// Default maximum size of a message received over TCP. Larger messages
+/// are considered as malformed, instead of being buffered.
+const SNMP_DEFAULT_MAX_MESSAGE_SIZE: usize = 65535;
+
+static mut SNMP_MAX_MESSAGE_SIZE: usize = SNMP_DEFAULT_MAX_MESSAGE_SIZE;
 
+/// Default time to wait for a response, in seconds, before a request is
+/// considered as unanswered
+const SNMP_DEFAULT_RESPONSE_TIMEOUT: u64 = 10;
+
+static mut SNMP_RESPONSE_TIMEOUT: u64 = SNMP_DEFAULT_RESPONSE_TIMEOUT;
+
+pub struct SNMPState {
     /// List of transactions for this session
-    transactions: Vec<SNMPTransaction<'a>>,
+    transactions: VecDeque<SNMPTransaction>,
 
     /// tx counter for assigning incrementing id's to tx's
     tx_id: u64,
+
+    /// Maximum number of live transactions. When reached, the oldest complete
+    /// transaction is dropped, or the new one is refused if none is complete.
+    max_tx: usize,
+
+    /// Maximum value of max-repetitions in a GetBulkRequest, before an
+    /// event is raised (0 to disable)
+    max_bulk_repetitions: u32,
+
+    /// Maximum size of a message received over TCP
+    max_message_size: usize,
+
+    /// Time to wait for a response, in seconds (0 to wait until the end of
+    /// the flow)
+    response_timeout: u64,
+
+    /// Time of the last packet, in seconds
+    ts: u64,
+
+    /// True if a SNMPv1 or v2c message was seen on the flow
+    community_seen: bool,
 }
 
-pub struct SNMPPduInfo<'a> {
+pub struct SNMPPduInfo {
     pub pdu_type: PduType,
 
+    /// Request ID, used to pair responses with requests (0 for v1 traps)
+    pub request_id: i32,
+
     pub err: ErrorStatus,
 
-    pub trap_type: Option<(TrapType,Oid<'a>,NetworkAddress)>,
+    pub trap_type: Option<SnmpTrapV1Info>,
+
+    /// Parameters of a GetBulkRequest
+    pub bulk: Option<SnmpBulkInfo>,
+
+    vars: Vec<SnmpVarBind>,
+
+    /// Encoded OIDs of all varbinds, stored back to back so that a PDU
+    /// needs a single allocation for them
+    oids: Vec<u8>,
+}
+
+// RFC 3416, section 3 (error-status)
+const SNMP_ERROR_STATUS_NAMES: &[&str] = &[
+    "noError", "tooBig", "noSuchName", "badValue", "readOnly", "genErr",
+    "noAccess", "wrongType", "wrongLength", "wrongEncoding", "wrongValue",
+    "noCreation", "inconsistentValue", "resourceUnavailable", "commitFailed",
+    "undoFailed", "authorizationError", "notWritable", "inconsistentName",
+];
+
+/// Returns the name of an error-status code, if known
+pub fn snmp_error_status_name(code: u32) -> Option<&'static str> {
+    SNMP_ERROR_STATUS_NAMES.get(code as usize).copied()
+}
 
-    pub vars: Vec<Oid<'a>>,
+/// Returns the error-status code for a name (case-insensitive), if known
+pub fn snmp_error_status_from_name(name: &str) -> Option<u32> {
+    SNMP_ERROR_STATUS_NAMES.iter()
+        .position(|n| n.eq_ignore_ascii_case(name))
+        .map(|p| p as u32)
+}
+
+/// Content of a SNMPv1 Trap-PDU
+pub struct SnmpTrapV1Info {
+    pub generic_trap: TrapType,
+
+    pub enterprise: Oid<'static>,
+
+    pub agent_addr: NetworkAddress,
+
+    /// Specific code, only meaningful for enterprise specific traps
+    pub specific_trap: u32,
+
+    /// Time elapsed since the agent (re)started, in hundredths of seconds
+    pub timestamp: u32,
+}
+
+impl SnmpTrapV1Info {
+    /// Returns the specific trap code, if the trap is enterprise specific
+    pub fn specific_code(&self) -> Option<u32> {
+        if self.generic_trap == TrapType::ENTERPRISE_SPECIFIC {
+            Some(self.specific_trap)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parameters of a GetBulkRequest (RFC 3416, section 4.2.3)
+#[derive(Debug,PartialEq)]
+pub struct SnmpBulkInfo {
+    /// Number of variables that are not repeated
+    pub non_repeaters: u32,
+
+    /// Number of repetitions requested for the other variables
+    pub max_repetitions: u32,
+}
+
+/// Value bound to an OID in a variable binding
+#[derive(Debug,PartialEq)]
+pub enum SnmpValue {
+    Integer(i64),
+    OctetString(Vec<u8>),
+    Oid(Oid<'static>),
+    IpAddress(Ipv4Addr),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Counter64(u64),
+    Null,
+    /// Any other syntax (opaque, bit string, exceptions, etc.)
+    Unknown,
+}
+
+#[derive(Debug,PartialEq)]
+struct SnmpVarBind {
+    /// Position of the OID in `SNMPPduInfo::oids`
+    oid: Range<usize>,
+    value: SnmpValue,
+}
+
+// RFC 3412, section 6.4 (msgFlags)
+bitflags! {
+    /// Security level of an SNMPv3 message
+    pub struct SNMPSecurityFlags: u8 {
+        /// Message is authenticated (authNoPriv or authPriv)
+        const AUTH = 0x1;
+        /// Scoped PDU is encrypted (authPriv)
+        const PRIV = 0x2;
+        /// Receiver must send a Report PDU if the message is dropped
+        const REPORTABLE = 0x4;
+    }
 }
 
-pub struct SNMPTransaction<'a> {
+/// An SNMP transaction: a message, and its response if paired.
+///
+/// Transactions are bidirectional: a request is complete in the direction
+/// of the response once it is paired, or once it expires, either because no
+/// response was seen within the response timeout, or because there are too
+/// many live transactions. Other messages (responses without a request,
+/// traps, reports) are complete in both directions as soon as they are
+/// parsed.
+///
+/// All the data stored in a transaction is owned. The input buffer passed to
+/// `parse` is only valid for the duration of the call (UDP payload of the
+/// current packet, or TCP stream data that is released once consumed), while
+/// transactions live until freed by the app-layer, possibly several packets
+/// later, so nothing can borrow from it. The dotted and symbolic forms of
+/// the OIDs are only built when detection or logging asks for them.
+pub struct SNMPTransaction {
     /// PDU version
     pub version: u32,
 
+    /// Direction of the message that created the transaction (STREAM_TOSERVER
+    /// or STREAM_TOCLIENT). A paired response does not change it.
+    pub direction: u8,
+
     /// PDU info, if present (and cleartext)
-    pub info: Option<SNMPPduInfo<'a>>,
+    pub info: Option<SNMPPduInfo>,
+
+    /// PDU info of the response, if paired with the request of this transaction
+    pub response_info: Option<SNMPPduInfo>,
 
     /// Community, if present (SNMPv2)
     pub community: Option<String>,
@@ -72,9 +252,50 @@ pub struct SNMPTransaction<'a> {
     /// USM info, if present (SNMPv3)
     pub usm: Option<String>,
 
+    /// Authoritative engine ID, if present (SNMPv3 with USM)
+    pub engine_id: Option<Vec<u8>>,
+
+    /// Message flags, if present (SNMPv3)
+    pub security_flags: Option<SNMPSecurityFlags>,
+
+    /// Message ID, if present (SNMPv3)
+    pub msg_id: Option<u32>,
+
+    /// Maximum message size supported by the sender, if present (SNMPv3)
+    pub msg_max_size: Option<u32>,
+
+    /// Security model (msgSecurityModel, for ex. 3 for USM), if present
+    /// (SNMPv3)
+    pub security_model: Option<u32>,
+
     /// True if transaction was encrypted
     pub encrypted: bool,
 
+    /// True if the scoped PDU was decrypted using a configured key
+    pub decrypted: bool,
+
+    /// True if an encrypted response was paired with the request (SNMPv3)
+    pub response_encrypted: bool,
+
+    /// True if the message or its response is a Report PDU, usually sent by
+    /// an engine rejecting a message (unknown user or engine, wrong digest,
+    /// not in time window)
+    pub report: bool,
+
+    /// True if the request will not be paired with a response anymore
+    expired: bool,
+
+    /// Time of the message, in seconds
+    ts: u64,
+
+    /// Dotted form of the OIDs of the varbinds, built when first requested
+    /// by detection, in the order of `varbinds`
+    oid_strs: Vec<String>,
+
+    /// Symbolic names (or dotted form if unknown) of the OIDs of the
+    /// varbinds, built when first requested, in the order of `varbinds`
+    oid_names: Vec<String>,
+
     /// The internal transaction id
     id: u64,
 
@@ -84,112 +305,433 @@ pub struct SNMPTransaction<'a> {
     /// The events associated with this transaction
     events: *mut core::AppLayerDecoderEvents,
 
+    /// Events set on the transaction, as the event list is not available in
+    /// the tests
+    #[cfg(test)]
+    test_events: Vec<u8>,
+
     tx_data: applayer::AppLayerTxData,
 }
 
 
 
-impl<'a> SNMPState<'a> {
-    pub fn new() -> SNMPState<'a> {
+impl SNMPState {
+    pub fn new() -> SNMPState {
         SNMPState{
-            version: 0,
-            transactions: Vec::new(),
+            transactions: VecDeque::new(),
             tx_id: 0,
+            max_tx: unsafe { SNMP_MAX_TX },
+            max_bulk_repetitions: unsafe { SNMP_MAX_BULK_REPETITIONS },
+            max_message_size: unsafe { SNMP_MAX_MESSAGE_SIZE },
+            response_timeout: unsafe { SNMP_RESPONSE_TIMEOUT },
+            ts: 0,
+            community_seen: false,
         }
     }
 }
 
-impl<'a> Default for SNMPPduInfo<'a> {
-    fn default() -> SNMPPduInfo<'a> {
+impl Default for SNMPPduInfo {
+    fn default() -> SNMPPduInfo {
         SNMPPduInfo{
             pdu_type: PduType(0),
+            request_id: 0,
             err: ErrorStatus::NoError,
             trap_type: None,
-            vars: Vec::new()
+            bulk: None,
+            vars: Vec::new(),
+            oids: Vec::new(),
+        }
+    }
+}
+
+impl SnmpValue {
+    fn new(val: &ObjectSyntax) -> SnmpValue {
+        match *val {
+            ObjectSyntax::Number(ref obj) => {
+                match ber_integer_value(obj) {
+                    Some(n) => SnmpValue::Integer(n),
+                    None    => SnmpValue::Unknown,
+                }
+            },
+            ObjectSyntax::String(s)          => SnmpValue::OctetString(s.to_vec()),
+            ObjectSyntax::Object(ref oid)    => SnmpValue::Oid(oid.to_owned()),
+            ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)) => SnmpValue::IpAddress(ip),
+            ObjectSyntax::Counter32(n)       => SnmpValue::Counter32(n),
+            ObjectSyntax::Gauge32(n)         => SnmpValue::Gauge32(n),
+            ObjectSyntax::TimeTicks(n)       => SnmpValue::TimeTicks(n),
+            ObjectSyntax::Counter64(n)       => SnmpValue::Counter64(n),
+            ObjectSyntax::Empty              => SnmpValue::Null,
+            _                                => SnmpValue::Unknown,
         }
     }
 }
 
-impl<'a> SNMPState<'a> {
-    fn add_pdu_info(&mut self, pdu: &SnmpPdu<'a>, tx: &mut SNMPTransaction<'a>) {
+// Decode a BER INTEGER as a signed value, if it fits in 64 bits
+fn ber_integer_value(obj: &BerObject) -> Option<i64> {
+    match obj.content {
+        BerObjectContent::Integer(i) if !i.is_empty() && i.len() <= 8 => {
+            let init = if i[0] & 0x80 != 0 { -1 } else { 0 };
+            Some(i.iter().fold(init, |acc, &b| (acc << 8) | b as i64))
+        },
+        _ => None,
+    }
+}
+
+impl SNMPPduInfo {
+    /// Iterate over the varbinds, as OID and value
+    pub fn varbinds(&self) -> impl Iterator<Item=(Oid<'_>, &SnmpValue)> {
+        self.vars.iter().map(move |var| {
+            (Oid::new(Cow::Borrowed(&self.oids[var.oid.clone()])), &var.value)
+        })
+    }
+
+    /// Returns true if the PDU is a request waiting for a response
+    fn expects_response(&self) -> bool {
+        match self.pdu_type {
+            PduType::GetRequest |
+            PduType::GetNextRequest |
+            PduType::SetRequest |
+            PduType::GetBulkRequest |
+            PduType::InformRequest => true,
+            _                      => false,
+        }
+    }
+
+    /// Returns true if the PDU type can be carried by a message of the
+    /// given version (RFC 3416 and RFC 3584, section 2.1)
+    fn is_valid_for_version(&self, version: u32) -> bool {
+        match self.pdu_type {
+            PduType::TrapV1         => version == 1,
+            PduType::GetBulkRequest |
+            PduType::InformRequest  |
+            PduType::TrapV2         |
+            PduType::Report         => version != 1,
+            _                       => true,
+        }
+    }
+}
+
+impl SNMPState {
+    fn get_pdu_info(pdu: &SnmpPdu) -> SNMPPduInfo {
         let mut pdu_info = SNMPPduInfo::default();
         pdu_info.pdu_type = pdu.pdu_type();
         match *pdu {
             SnmpPdu::Generic(ref pdu) => {
+                pdu_info.request_id = pdu.req_id as i32;
                 pdu_info.err = pdu.err;
             },
-            SnmpPdu::Bulk(_) => {
+            SnmpPdu::Bulk(ref pdu) => {
+                pdu_info.request_id = pdu.req_id as i32;
+                pdu_info.bulk = Some(SnmpBulkInfo{
+                    non_repeaters: pdu.non_repeaters,
+                    max_repetitions: pdu.max_repetitions,
+                });
             },
             SnmpPdu::TrapV1(ref t)    => {
-                pdu_info.trap_type = Some((t.generic_trap,t.enterprise.clone(),t.agent_addr));
+                pdu_info.trap_type = Some(SnmpTrapV1Info{
+                    generic_trap: t.generic_trap,
+                    enterprise: t.enterprise.to_owned(),
+                    agent_addr: t.agent_addr,
+                    specific_trap: t.specific_trap,
+                    timestamp: t.timestamp,
+                });
             }
         }
 
+        pdu_info.vars.reserve_exact(pdu.vars_iter().len());
+        pdu_info.oids.reserve_exact(pdu.vars_iter().map(|var| var.oid.bytes().len()).sum());
         for var in pdu.vars_iter() {
-            pdu_info.vars.push(var.oid.to_owned());
+            let start = pdu_info.oids.len();
+            pdu_info.oids.extend_from_slice(var.oid.bytes());
+            pdu_info.vars.push(SnmpVarBind{
+                oid: start..pdu_info.oids.len(),
+                value: SnmpValue::new(&var.val),
+            });
+        }
+        pdu_info
+    }
+
+    /// Returns true if the PDU is a GetBulkRequest asking for too many
+    /// repetitions
+    fn is_excessive_bulk(&self, info: &SNMPPduInfo) -> bool {
+        match info.bulk {
+            Some(ref bulk) => self.max_bulk_repetitions > 0 &&
+                bulk.max_repetitions > self.max_bulk_repetitions,
+            None           => false,
         }
-        tx.info = Some(pdu_info);
     }
 
-    fn handle_snmp_v12(&mut self, msg: SnmpMessage<'a>, _direction: u8) -> i32 {
-        let mut tx = self.new_tx();
+    /// Find the transaction of the request matching a response, if any
+    fn find_request_tx(&mut self, request_id: i32, direction: u8) -> Option<&mut SNMPTransaction> {
+        self.transactions.iter_mut().rev().find(|tx| {
+            !tx.expired && tx.direction != direction && !tx.has_response() && match tx.info {
+                Some(ref info) => info.expects_response() && info.request_id == request_id,
+                None           => false,
+            }
+        })
+    }
+
+    /// Find the transaction of the SNMPv3 request matching the msgID of a
+    /// response, if any. If `reportable` is set, only requests with the
+    /// reportable flag are considered.
+    fn find_v3_request_tx(&mut self, msg_id: u32, direction: u8, reportable: bool) -> Option<&mut SNMPTransaction> {
+        self.transactions.iter_mut().rev().find(|tx| {
+            !tx.expired && tx.direction != direction && tx.msg_id == Some(msg_id) &&
+                tx.expects_response() && !tx.has_response() &&
+                (!reportable || tx.security_flags.map_or(false, |flags| {
+                    flags.contains(SNMPSecurityFlags::REPORTABLE)
+                }))
+        })
+    }
+
+    /// Attach a response to the transaction of the matching request.
+    ///
+    /// Returns the id of the transaction, or the PDU info back if it was
+    /// not paired.
+    fn pair_response(&mut self, info: SNMPPduInfo, direction: u8) -> Result<u64, SNMPPduInfo> {
+        if info.pdu_type != PduType::Response {
+            return Err(info);
+        }
+        match self.find_request_tx(info.request_id, direction) {
+            Some(tx) => {
+                SCLogDebug!("SNMP response paired with request {}", info.request_id);
+                tx.response_info = Some(info);
+                Ok(tx.id)
+            },
+            None     => Err(info),
+        }
+    }
+
+    /// Handle a SNMPv1 or v2c message, and return the id of the transaction
+    /// it was stored in
+    fn handle_snmp_v12(&mut self, msg: SnmpMessage, direction: u8) -> u64 {
+        self.community_seen = true;
+        let info = match self.pair_response(Self::get_pdu_info(&msg.pdu), direction) {
+            Err(info) => info,
+            Ok(id)    => return id,
+        };
         // in the message, version is encoded as 0 (version 1) or 1 (version 2)
-        if self.version != msg.version + 1 {
-            SCLogDebug!("SNMP version mismatch: expected {}, received {}", self.version, msg.version+1);
+        let version = msg.version + 1;
+        let mut tx = self.new_tx(version, direction);
+        if !info.is_valid_for_version(version) {
+            SCLogDebug!("SNMP version mismatch: PDU {:?} in version {}", info.pdu_type, version);
             self.set_event_tx(&mut tx, SNMPEvent::VersionMismatch);
         }
-        self.add_pdu_info(&msg.pdu, &mut tx);
+        tx.check_report(&info);
+        if self.is_excessive_bulk(&info) {
+            self.set_event_tx(&mut tx, SNMPEvent::ExcessiveBulkRepetitions);
+        }
+        tx.info = Some(info);
         tx.community = Some(msg.community);
-        self.transactions.push(tx);
-        0
+        self.push_tx(tx)
     }
 
-    fn handle_snmp_v3(&mut self, msg: SnmpV3Message<'a>, _direction: u8) -> i32 {
-        let mut tx = self.new_tx();
-        if self.version != msg.version {
-            SCLogDebug!("SNMP version mismatch: expected {}, received {}", self.version, msg.version);
-            self.set_event_tx(&mut tx, SNMPEvent::VersionMismatch);
+    /// Decrypt the scoped PDU of a SNMPv3 message, if a key is configured
+    /// for the engine and user.
+    #[cfg(feature = "snmp-decrypt")]
+    fn decrypt_pdu_info(params: &SecurityParameters, data: &[u8]) -> Option<SNMPPduInfo> {
+        match *params {
+            SecurityParameters::USM(ref usm) => {
+                let scoped = usm::snmp_usm_decrypt(usm, data)?;
+                Self::get_scoped_pdu_info(&scoped)
+            },
+            _ => None,
+        }
+    }
+
+    #[cfg(not(feature = "snmp-decrypt"))]
+    fn decrypt_pdu_info(_params: &SecurityParameters, _data: &[u8]) -> Option<SNMPPduInfo> {
+        None
+    }
+
+    /// Parse a decrypted scoped PDU. The PDU is wrapped in a SNMPv2c message
+    /// so that it can be read by the PDU parser of the SNMP crate.
+    #[cfg(feature = "snmp-decrypt")]
+    fn get_scoped_pdu_info(i: &[u8]) -> Option<SNMPPduInfo> {
+        // remove the padding, if any
+        let (rem, size) = parse_snmp_message_length(i).ok()?;
+        let content_len = size - (i.len() - rem.len());
+        let rem = rem.get(..content_len)?;
+        // skip contextEngineID and contextName
+        let (rem, _) = parse_ber_octetstring(rem).ok()?;
+        let (pdu, _) = parse_ber_octetstring(rem).ok()?;
+        let mut content = vec![0x02, 0x01, 0x01, 0x04, 0x00];
+        content.extend_from_slice(pdu);
+        let mut msg = ber_sequence_header(content.len());
+        msg.extend_from_slice(&content);
+        match parse_snmp_v2c(&msg) {
+            Ok((_, m)) => Some(Self::get_pdu_info(&m.pdu)),
+            Err(_)     => None,
         }
-        match msg.data {
-            ScopedPduData::Plaintext(pdu) => {
-                self.add_pdu_info(&pdu.data, &mut tx);
+    }
+
+    /// Handle a SNMPv3 message, and return the id of the transaction it was
+    /// stored in
+    fn handle_snmp_v3(&mut self, msg: SnmpV3Message, direction: u8) -> u64 {
+        let mut decrypted = false;
+        let pdu_info = match msg.data {
+            ScopedPduData::Plaintext(ref pdu) => Some(Self::get_pdu_info(&pdu.data)),
+            ScopedPduData::Encrypted(data)    => {
+                let info = Self::decrypt_pdu_info(&msg.security_params, data);
+                decrypted = info.is_some();
+                info
             },
-            _                             => {
-                tx.encrypted = true;
+        };
+        let flags = SNMPSecurityFlags::from_bits_truncate(msg.header_data.msg_flags);
+        let msg_id = msg.header_data.msg_id;
+        // responses and reports use the msgID of the request, which is
+        // available even if the PDU is encrypted (RFC 3412, section 7.1)
+        // An encrypted PDU without the reportable flag is either a response
+        // or an unconfirmed class PDU (trap, InformResponse), so it is only
+        // paired with a reportable request from the other side.
+        let is_response = match pdu_info {
+            Some(ref info) => info.pdu_type == PduType::Response || info.pdu_type == PduType::Report,
+            None           => !flags.contains(SNMPSecurityFlags::REPORTABLE),
+        };
+        let pdu_info = if is_response {
+            match self.find_v3_request_tx(msg_id, direction, pdu_info.is_none()) {
+                Some(tx) => {
+                    SCLogDebug!("SNMPv3 response paired with request msgID {}", msg_id);
+                    match pdu_info {
+                        Some(info) => {
+                            tx.check_report(&info);
+                            tx.response_info = Some(info);
+                        },
+                        None       => tx.response_encrypted = true,
+                    }
+                    return tx.id;
+                },
+                None     => pdu_info,
+            }
+        } else {
+            pdu_info
+        };
+        let mut info = None;
+        if let Some(pdu_info) = pdu_info {
+            match self.pair_response(pdu_info, direction) {
+                Err(i) => info = Some(i),
+                Ok(id) => return id,
+            }
+        }
+        let mut tx = self.new_tx(msg.version, direction);
+        if let Some(ref info) = info {
+            if !info.is_valid_for_version(msg.version) {
+                SCLogDebug!("SNMP version mismatch: PDU {:?} in version {}", info.pdu_type, msg.version);
+                self.set_event_tx(&mut tx, SNMPEvent::VersionMismatch);
+            }
+            tx.check_report(info);
+        }
+        tx.security_flags = Some(flags);
+        tx.msg_id = Some(msg_id);
+        tx.msg_max_size = Some(msg.header_data.msg_max_size);
+        tx.security_model = Some(msg.header_data.msg_security_model.0);
+        if self.community_seen && !flags.contains(SNMPSecurityFlags::AUTH) {
+            // possibly injected in a community based management conversation
+            SCLogDebug!("SNMPv3 noAuthNoPriv message in a SNMPv1/v2c flow");
+            self.set_event_tx(&mut tx, SNMPEvent::UnauthenticatedV3InCommunityFlow);
+        }
+        if let Some(ref info) = info {
+            if self.is_excessive_bulk(info) {
+                self.set_event_tx(&mut tx, SNMPEvent::ExcessiveBulkRepetitions);
             }
         }
+        tx.encrypted = info.is_none();
+        tx.decrypted = decrypted;
+        tx.info = info;
         match msg.security_params {
             SecurityParameters::USM(usm) => {
+                tx.engine_id = Some(usm.msg_authoritative_engine_id.to_vec());
                 tx.usm = Some(usm.msg_user_name);
             },
             _                            => {
+                SCLogDebug!("SNMPv3 unknown security model {}", msg.header_data.msg_security_model.0);
                 self.set_event_tx(&mut tx, SNMPEvent::UnknownSecurityModel);
             }
         }
-        self.transactions.push(tx);
-        0
+        self.push_tx(tx)
     }
 
     /// Parse an SNMP request message
     ///
     /// Returns 0 if successful, or -1 on error
-    fn parse(&mut self, i: &'a [u8], direction: u8) -> i32 {
-        if self.version == 0 {
-            match parse_pdu_enveloppe_version(i) {
-                Ok((_,x)) => self.version = x,
-                _         => (),
-            }
+    pub fn parse(&mut self, i: &[u8], direction: u8) -> i32 {
+        // A datagram may carry several messages back to back (some trap
+        // relays batch notifications), so parse until the input is exhausted.
+        let mut cur_i = i;
+        // transaction of the last message parsed
+        let mut last_tx = None;
+        while cur_i.len() > 0 {
+            let tx_id = match parse_snmp_generic_message(cur_i) {
+                Ok((rem,SnmpGenericMessage::V1(msg))) |
+                Ok((rem,SnmpGenericMessage::V2(msg))) => {
+                    cur_i = rem;
+                    self.handle_snmp_v12(msg, direction)
+                },
+                Ok((rem,SnmpGenericMessage::V3(msg))) => {
+                    cur_i = rem;
+                    self.handle_snmp_v3(msg, direction)
+                },
+                Err(nom::Err::Incomplete(_)) => {
+                    // only a message longer than the data is truncated: an
+                    // inner element overrunning its parent is malformed
+                    let event = match parse_snmp_message_length(cur_i) {
+                        Ok((_, len)) if len > cur_i.len() => SNMPEvent::TruncatedPdu,
+                        Err(nom::Err::Incomplete(_))      => SNMPEvent::TruncatedPdu,
+                        _                                 => SNMPEvent::MalformedData,
+                    };
+                    SCLogDebug!("parse_snmp failed: incomplete data");
+                    self.set_event_parsed(last_tx, event);
+                    return if last_tx.is_some() { 0 } else { -1 };
+                },
+                Err(_e) => {
+                    SCLogDebug!("parse_snmp failed: {:?}", _e);
+                    self.set_event_parsed(last_tx, SNMPEvent::MalformedData);
+                    // keep the messages parsed so far, drop the remnant
+                    return if last_tx.is_some() { 0 } else { -1 };
+                },
+            };
+            last_tx = Some(tx_id);
         }
-        match parse_snmp_generic_message(i) {
-            Ok((_rem,SnmpGenericMessage::V1(msg))) |
-            Ok((_rem,SnmpGenericMessage::V2(msg))) => self.handle_snmp_v12(msg, direction),
-            Ok((_rem,SnmpGenericMessage::V3(msg))) => self.handle_snmp_v3(msg, direction),
-            Err(_e) => {
-                SCLogDebug!("parse_snmp failed: {:?}", _e);
-                self.set_event(SNMPEvent::MalformedData);
-                -1
-            },
+        0
+    }
+
+    /// Parse SNMP messages received over TCP
+    ///
+    /// Over TCP (RFC 3430), messages are sent back to back without any
+    /// additional framing, so the BER length of the outer sequence is used
+    /// to split the stream. A message may span several segments.
+    fn parse_tcp(&mut self, i: &[u8], direction: u8) -> AppLayerResult {
+        let mut cur_i = i;
+        let mut consumed = 0;
+        while cur_i.len() > 0 {
+            match parse_snmp_message_length(cur_i) {
+                Ok((_, size)) => {
+                    if size > self.max_message_size {
+                        SCLogDebug!("SNMP message too large: {}", size);
+                        self.set_event(SNMPEvent::MalformedData);
+                        return AppLayerResult::err();
+                    }
+                    if cur_i.len() < size {
+                        SCLogDebug!("Not enough SNMP data to parse. Returning {}/{}",
+                                    consumed, size);
+                        return AppLayerResult::incomplete(consumed as u32, size as u32);
+                    }
+                    if self.parse(&cur_i[..size], direction) < 0 {
+                        return AppLayerResult::err();
+                    }
+                    cur_i = &cur_i[size..];
+                    consumed += size;
+                },
+                Err(nom::Err::Incomplete(_)) => {
+                    return AppLayerResult::incomplete(consumed as u32, (cur_i.len() + 1) as u32);
+                },
+                Err(_) => {
+                    self.set_event(SNMPEvent::MalformedData);
+                    return AppLayerResult::err();
+                },
+            }
         }
+        AppLayerResult::ok()
     }
 
     fn free(&mut self) {
@@ -198,17 +740,77 @@ impl<'a> SNMPState<'a> {
         self.transactions.clear();
     }
 
-    fn new_tx(&mut self) -> SNMPTransaction<'a> {
+    /// Store a new transaction. If there are too many live transactions, the
+    /// oldest complete one is dropped, and an event is set on the new one. If
+    /// none is complete, the new transaction is refused, and the event is
+    /// set on the most recent one.
+    ///
+    /// Returns the id of the transaction the message was stored in, or of
+    /// the most recent one if refused.
+    fn push_tx(&mut self, mut tx: SNMPTransaction) -> u64 {
+        if self.transactions.len() >= self.max_tx {
+            match self.transactions.iter().position(|tx| tx.is_complete()) {
+                Some(idx) => {
+                    let _old = self.transactions.remove(idx);
+                    SCLogDebug!("SNMP transaction {:?} dropped", _old.map(|tx| tx.id));
+                    self.set_event_tx(&mut tx, SNMPEvent::TooManyTransactions);
+                },
+                None      => {
+                    SCLogDebug!("SNMP transaction {} refused", tx.id);
+                    self.set_event(SNMPEvent::TooManyTransactions);
+                    return self.transactions.back().map_or(tx.id, |tx| tx.id);
+                },
+            }
+        }
+        let id = tx.id;
+        self.transactions.push_back(tx);
+        id
+    }
+
+    fn new_tx(&mut self, version: u32, direction: u8) -> SNMPTransaction {
         self.tx_id += 1;
-        SNMPTransaction::new(self.version, self.tx_id)
+        let mut tx = SNMPTransaction::new(version, self.tx_id, direction);
+        tx.ts = self.ts;
+        tx
+    }
+
+    /// Update the current time, and expire the requests that were not
+    /// answered within the response timeout.
+    fn update_ts(&mut self, ts: u64) {
+        self.ts = ts;
+        if self.response_timeout == 0 {
+            return;
+        }
+        // transactions are stored in the order of their creation time
+        for tx in self.transactions.iter_mut() {
+            if tx.ts.saturating_add(self.response_timeout) > ts {
+                break;
+            }
+            if !tx.is_complete() {
+                SCLogDebug!("SNMP request {} timed out", tx.id);
+                tx.expired = true;
+            }
+        }
+    }
+
+    /// Find the index of a transaction. Transactions are stored in the order
+    /// of their ids, so a binary search can be used.
+    fn tx_index(&self, tx_id: u64) -> Option<usize> {
+        let (front, back) = self.transactions.as_slices();
+        match front.binary_search_by_key(&(tx_id + 1), |tx| tx.id) {
+            Ok(idx) => Some(idx),
+            Err(_)  => back.binary_search_by_key(&(tx_id + 1), |tx| tx.id).ok()
+                .map(|idx| front.len() + idx),
+        }
     }
 
-    fn get_tx_by_id(&mut self, tx_id: u64) -> Option<&SNMPTransaction> {
-        self.transactions.iter().rev().find(|&tx| tx.id == tx_id + 1)
+    pub fn get_tx_by_id(&mut self, tx_id: u64) -> Option<&SNMPTransaction> {
+        let idx = self.tx_index(tx_id)?;
+        Some(&self.transactions[idx])
     }
 
     fn free_tx(&mut self, tx_id: u64) {
-        let tx = self.transactions.iter().position(|tx| tx.id == tx_id + 1);
+        let tx = self.tx_index(tx_id);
         debug_assert!(tx != None);
         if let Some(idx) = tx {
             let _ = self.transactions.remove(idx);
@@ -217,15 +819,26 @@ impl<'a> SNMPState<'a> {
 
     /// Set an event. The event is set on the most recent transaction.
     fn set_event(&mut self, event: SNMPEvent) {
-        if let Some(tx) = self.transactions.last_mut() {
-            let ev = event as u8;
-            core::sc_app_layer_decoder_events_set_event_raw(&mut tx.events, ev);
+        if let Some(tx) = self.transactions.back_mut() {
+            tx.set_event(event);
+        }
+    }
+
+    /// Set an event on the transaction of the last message parsed, if any,
+    /// else on the most recent transaction.
+    fn set_event_parsed(&mut self, tx_id: Option<u64>, event: SNMPEvent) {
+        let tx_id = match tx_id {
+            Some(id) => id,
+            None     => return self.set_event(event),
+        };
+        if let Some(idx) = self.tx_index(tx_id - 1) {
+            self.transactions[idx].set_event(event);
         }
     }
 
     /// Set an event on a specific transaction.
     fn set_event_tx(&self, tx: &mut SNMPTransaction, event: SNMPEvent) {
-        core::sc_app_layer_decoder_events_set_event_raw(&mut tx.events, event as u8);
+        tx.set_event(event);
     }
 
     // for use with the C API call StateGetTxIterator
@@ -251,21 +864,112 @@ impl<'a> SNMPState<'a> {
     }
 }
 
-impl<'a> SNMPTransaction<'a> {
-    pub fn new(version: u32, id: u64) -> SNMPTransaction<'a> {
+impl SNMPTransaction {
+    pub fn new(version: u32, id: u64, direction: u8) -> SNMPTransaction {
         SNMPTransaction {
             version,
+            direction,
             info: None,
+            response_info: None,
             community: None,
             usm: None,
+            engine_id: None,
+            security_flags: None,
+            msg_id: None,
+            msg_max_size: None,
+            security_model: None,
             encrypted: false,
+            decrypted: false,
+            response_encrypted: false,
+            report: false,
+            expired: false,
+            ts: 0,
+            oid_strs: Vec::new(),
+            oid_names: Vec::new(),
             id: id,
             de_state: None,
             events: std::ptr::null_mut(),
+            #[cfg(test)]
+            test_events: Vec::new(),
             tx_data: applayer::AppLayerTxData::new(),
         }
     }
 
+    fn set_event(&mut self, event: SNMPEvent) {
+        let ev = event as u8;
+        core::sc_app_layer_decoder_events_set_event_raw(&mut self.events, ev);
+        #[cfg(test)]
+        self.test_events.push(ev);
+    }
+
+    #[cfg(test)]
+    fn has_event(&self, event: SNMPEvent) -> bool {
+        self.test_events.contains(&(event as u8))
+    }
+
+    /// Flag the transaction and set the event if the PDU, of the message or
+    /// of its response, is a Report
+    fn check_report(&mut self, info: &SNMPPduInfo) {
+        if info.pdu_type == PduType::Report {
+            self.report = true;
+            self.set_event(SNMPEvent::Report);
+        }
+    }
+
+    /// Iterate over the varbinds of the request, then those of the response
+    pub fn varbinds(&self) -> impl Iterator<Item=(Oid<'_>, &SnmpValue)> {
+        self.info.iter().chain(self.response_info.iter()).flat_map(|info| info.varbinds())
+    }
+
+    /// Get the dotted form of the OID of a varbind (indexed as in `varbinds`).
+    /// Strings are built on the first call and kept, so the returned buffer
+    /// remains valid as long as the transaction is not freed.
+    pub fn oid_str(&mut self, index: usize) -> Option<&str> {
+        let built = self.oid_strs.len();
+        if index >= built {
+            let oids : Vec<String> = self.varbinds().skip(built).take(index + 1 - built)
+                .map(|(oid, _)| oid.to_string()).collect();
+            self.oid_strs.extend(oids);
+        }
+        self.oid_strs.get(index).map(|s| s.as_str())
+    }
+
+    /// Get the symbolic name of the OID of a varbind, or its dotted form if
+    /// OID names are not configured or the OID is unknown. Names are kept
+    /// as in `oid_str`.
+    pub fn oid_name(&mut self, index: usize) -> Option<&str> {
+        let built = self.oid_names.len();
+        if index >= built {
+            let names : Vec<String> = self.varbinds().skip(built).take(index + 1 - built)
+                .map(|(oid, _)| mib::snmp_oid_name(&oid).unwrap_or_else(|| oid.to_string()))
+                .collect();
+            self.oid_names.extend(names);
+        }
+        self.oid_names.get(index).map(|s| s.as_str())
+    }
+
+    /// Returns true if the message of the transaction expects a response
+    fn expects_response(&self) -> bool {
+        match self.info {
+            Some(ref info) => info.expects_response(),
+            // only confirmed class PDUs have the reportable flag
+            // (RFC 3412, section 6.4)
+            None           => self.encrypted && self.security_flags.map_or(false, |flags| {
+                flags.contains(SNMPSecurityFlags::REPORTABLE)
+            }),
+        }
+    }
+
+    /// Returns true if a response was paired with the transaction
+    pub fn has_response(&self) -> bool {
+        self.response_info.is_some() || self.response_encrypted
+    }
+
+    /// Returns true if no more messages are expected for this transaction
+    fn is_complete(&self) -> bool {
+        self.expired || !self.expects_response() || self.has_response()
+    }
+
     fn free(&mut self) {
         if self.events != std::ptr::null_mut() {
             core::sc_app_layer_decoder_events_free_events(&mut self.events);
@@ -273,7 +977,7 @@ impl<'a> SNMPTransaction<'a> {
     }
 }
 
-impl<'a> Drop for SNMPTransaction<'a> {
+impl Drop for SNMPTransaction {
     fn drop(&mut self) {
         self.free();
     }
@@ -301,7 +1005,7 @@ pub extern "C" fn rs_snmp_state_free(state: *mut std::os::raw::c_void) {
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn rs_snmp_parse_request(_flow: *const core::Flow,
+pub unsafe extern "C" fn rs_snmp_parse_request(flow: *const core::Flow,
                                        state: *mut std::os::raw::c_void,
                                        _pstate: *mut std::os::raw::c_void,
                                        input: *const u8,
@@ -310,11 +1014,13 @@ pub unsafe extern "C" fn rs_snmp_parse_request(_flow: *const core::Flow,
                                        _flags: u8) -> AppLayerResult {
     let buf = build_slice!(input,input_len as usize);
     let state = cast_pointer!(state,SNMPState);
+    let flow = cast_pointer!(flow,Flow);
+    state.update_ts(flow.get_last_time().as_secs());
     state.parse(buf, STREAM_TOSERVER).into()
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn rs_snmp_parse_response(_flow: *const core::Flow,
+pub unsafe extern "C" fn rs_snmp_parse_response(flow: *const core::Flow,
                                        state: *mut std::os::raw::c_void,
                                        _pstate: *mut std::os::raw::c_void,
                                        input: *const u8,
@@ -323,9 +1029,41 @@ pub unsafe extern "C" fn rs_snmp_parse_response(_flow: *const core::Flow,
                                        _flags: u8) -> AppLayerResult {
     let buf = build_slice!(input,input_len as usize);
     let state = cast_pointer!(state,SNMPState);
+    let flow = cast_pointer!(flow,Flow);
+    state.update_ts(flow.get_last_time().as_secs());
     state.parse(buf, STREAM_TOCLIENT).into()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_parse_request_tcp(flow: *const core::Flow,
+                                       state: *mut std::os::raw::c_void,
+                                       _pstate: *mut std::os::raw::c_void,
+                                       input: *const u8,
+                                       input_len: u32,
+                                       _data: *const std::os::raw::c_void,
+                                       _flags: u8) -> AppLayerResult {
+    let buf = build_slice!(input,input_len as usize);
+    let state = cast_pointer!(state,SNMPState);
+    let flow = cast_pointer!(flow,Flow);
+    state.update_ts(flow.get_last_time().as_secs());
+    state.parse_tcp(buf, STREAM_TOSERVER)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_parse_response_tcp(flow: *const core::Flow,
+                                       state: *mut std::os::raw::c_void,
+                                       _pstate: *mut std::os::raw::c_void,
+                                       input: *const u8,
+                                       input_len: u32,
+                                       _data: *const std::os::raw::c_void,
+                                       _flags: u8) -> AppLayerResult {
+    let buf = build_slice!(input,input_len as usize);
+    let state = cast_pointer!(state,SNMPState);
+    let flow = cast_pointer!(flow,Flow);
+    state.update_ts(flow.get_last_time().as_secs());
+    state.parse_tcp(buf, STREAM_TOCLIENT)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_snmp_state_get_tx(state: *mut std::os::raw::c_void,
                                       tx_id: u64)
@@ -355,11 +1093,14 @@ pub unsafe extern "C" fn rs_snmp_state_tx_free(state: *mut std::os::raw::c_void,
 }
 
 #[no_mangle]
-pub extern "C" fn rs_snmp_tx_get_alstate_progress(_tx: *mut std::os::raw::c_void,
-                                                 _direction: u8)
+pub unsafe extern "C" fn rs_snmp_tx_get_alstate_progress(tx: *mut std::os::raw::c_void,
+                                                 direction: u8)
                                                  -> std::os::raw::c_int
 {
-    1
+    let tx = cast_pointer!(tx,SNMPTransaction);
+    // the message of the transaction is complete in its own direction, the
+    // other direction waits for the response, if any, until it times out
+    if direction & tx.direction != 0 || tx.is_complete() { 1 } else { 0 }
 }
 
 #[no_mangle]
@@ -439,6 +1180,34 @@ pub unsafe extern "C" fn rs_snmp_get_tx_iterator(_ipproto: u8,
 
 static mut ALPROTO_SNMP : AppProto = ALPROTO_UNKNOWN;
 
+// Check that the header data of a SNMPv3 message has the expected types
+// and sane values (RFC 3412, section 6)
+fn is_snmp_v3_header(o: &BerObject) -> bool {
+    match o.content {
+        BerObjectContent::Sequence(ref v) if v.len() == 4 => {
+            let msg_id_ok = match v[0].content {
+                BerObjectContent::Integer(_) => true,
+                _                            => false,
+            };
+            // msgMaxSize is at least 484 bytes
+            let max_size_ok = match v[1].as_u32() {
+                Ok(n) => n >= 484 && n <= i32::MAX as u32,
+                _     => false,
+            };
+            let flags_ok = match v[2].content {
+                BerObjectContent::OctetString(s) => s.len() == 1,
+                _                                => false,
+            };
+            let model_ok = match v[3].as_u32() {
+                Ok(n) => n > 0 && n <= i32::MAX as u32,
+                _     => false,
+            };
+            msg_id_ok && max_size_ok && flags_ok && model_ok
+        },
+        _ => false,
+    }
+}
+
 // Read PDU sequence and extract version, if similar to SNMP definition
 fn parse_pdu_enveloppe_version(i:&[u8]) -> IResult<&[u8],u32> {
     match parse_der_sequence(i) {
@@ -446,13 +1215,22 @@ fn parse_pdu_enveloppe_version(i:&[u8]) -> IResult<&[u8],u32> {
             match x.content {
                 BerObjectContent::Sequence(ref v) => {
                     if v.len() == 3 {
+                        // the community must be an OCTET STRING
+                        let community_ok = match v[1].content {
+                            BerObjectContent::OctetString(_) => true,
+                            _                                => false,
+                        };
                         match v[0].as_u32()  {
-                            Ok(0) => { return Ok((i,1)); }, // possibly SNMPv1
-                            Ok(1) => { return Ok((i,2)); }, // possibly SNMPv2c
+                            Ok(0) if community_ok => { return Ok((i,1)); }, // possibly SNMPv1
+                            Ok(1) if community_ok => { return Ok((i,2)); }, // possibly SNMPv2c
                             _     => ()
                         }
-                    } else if v.len() == 4 && v[0].as_u32() == Ok(3) {
-                        return Ok((i,3)); // possibly SNMPv3
+                    } else if v.len() == 4 && v[0].as_u32() == Ok(3) &&
+                        is_snmp_v3_header(&v[1]) {
+                        // security parameters are an OCTET STRING
+                        if let BerObjectContent::OctetString(_) = v[2].content {
+                            return Ok((i,3)); // possibly SNMPv3
+                        }
                     }
                 },
                 _ => ()
@@ -465,6 +1243,50 @@ fn parse_pdu_enveloppe_version(i:&[u8]) -> IResult<&[u8],u32> {
     }
 }
 
+// Encode the header of a BER sequence of the given length
+#[cfg(feature = "snmp-decrypt")]
+fn ber_sequence_header(len: usize) -> Vec<u8> {
+    let mut hdr = vec![0x30];
+    if len < 0x80 {
+        hdr.push(len as u8);
+    } else {
+        let bytes : Vec<u8> = (len as u32).to_be_bytes().iter()
+            .skip_while(|&&b| b == 0).cloned().collect();
+        hdr.push(0x80 | bytes.len() as u8);
+        hdr.extend_from_slice(&bytes);
+    }
+    hdr
+}
+
+// Read the header of the outer BER sequence, and return the total length of
+// the message (header and content). Long form lengths are accepted on up to
+// 4 bytes, and must not exceed the maximum msgMaxSize (RFC 3412, section 6).
+fn parse_snmp_message_length(i:&[u8]) -> IResult<&[u8],usize> {
+    if i.len() < 2 {
+        return Err(nom::Err::Incomplete(nom::Needed::Size(2 - i.len())));
+    }
+    if i[0] != 0x30 {
+        return Err(nom::Err::Error(error_position!(i, ErrorKind::Tag)));
+    }
+    let (hdr_len, len) = if i[1] & 0x80 == 0 {
+        (2, i[1] as usize)
+    } else {
+        let n = (i[1] & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return Err(nom::Err::Error(error_position!(i, ErrorKind::LengthValue)));
+        }
+        if i.len() < 2 + n {
+            return Err(nom::Err::Incomplete(nom::Needed::Size(2 + n - i.len())));
+        }
+        let len = i[2..2 + n].iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (2 + n, len)
+    };
+    if len > i32::MAX as usize - hdr_len {
+        return Err(nom::Err::Error(error_position!(i, ErrorKind::LengthValue)));
+    }
+    Ok((&i[hdr_len..], hdr_len + len))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_snmp_probing_parser(_flow: *const Flow,
                                          _direction: u8,
@@ -481,26 +1303,95 @@ pub unsafe extern "C" fn rs_snmp_probing_parser(_flow: *const Flow,
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_snmp_probing_parser_tcp(flow: *const Flow,
+                                         direction: u8,
+                                         input:*const u8,
+                                         input_len: u32,
+                                         rdir: *mut u8) -> AppProto {
+    let slice = build_slice!(input,input_len as usize);
+    match parse_snmp_message_length(slice) {
+        Ok((_,size)) if slice.len() >= size => {
+            rs_snmp_probing_parser(flow, direction, input, size as u32, rdir)
+        },
+        Ok((rem,_)) => {
+            // message spans several segments, only check the version and
+            // the tag of the next element (community or header data)
+            match parse_ber_integer(rem) {
+                Ok((rem,v)) => {
+                    match (v.as_u32(), rem.first()) {
+                        (Ok(0), Some(0x04)) |
+                        (Ok(1), Some(0x04)) |
+                        (Ok(3), Some(0x30)) => ALPROTO_SNMP,
+                        (Ok(0), None) |
+                        (Ok(1), None) |
+                        (Ok(3), None)       => ALPROTO_UNKNOWN,
+                        _                   => ALPROTO_FAILED,
+                    }
+                },
+                Err(nom::Err::Incomplete(_)) => ALPROTO_UNKNOWN,
+                _                            => ALPROTO_FAILED,
+            }
+        },
+        Err(nom::Err::Incomplete(_)) => ALPROTO_UNKNOWN,
+        _                            => ALPROTO_FAILED,
+    }
+}
+
 export_tx_data_get!(rs_snmp_get_tx_data, SNMPTransaction);
 
 const PARSER_NAME : &'static [u8] = b"snmp\0";
 
+/// Parse the configuration shared by the UDP and TCP parsers. Called once,
+/// before the parsers are registered.
 #[no_mangle]
-pub unsafe extern "C" fn rs_register_snmp_parser() {
+pub unsafe extern "C" fn rs_snmp_parse_config() {
+    if let Some(val) = conf_get("app-layer.protocols.snmp.max-tx") {
+        match val.parse::<usize>() {
+            Ok(v) if v > 0 => SNMP_MAX_TX = v,
+            _              => { SCLogError!("Invalid value for snmp.max-tx: {}", val); },
+        }
+    }
+    if let Some(val) = conf_get("app-layer.protocols.snmp.max-bulk-repetitions") {
+        match val.parse::<u32>() {
+            Ok(v) => SNMP_MAX_BULK_REPETITIONS = v,
+            _     => { SCLogError!("Invalid value for snmp.max-bulk-repetitions: {}", val); },
+        }
+    }
+    if let Some(val) = conf_get("app-layer.protocols.snmp.max-message-size") {
+        match val.parse::<usize>() {
+            Ok(v) if v >= 484 && v <= i32::MAX as usize => SNMP_MAX_MESSAGE_SIZE = v,
+            _ => { SCLogError!("Invalid value for snmp.max-message-size: {}", val); },
+        }
+    }
+    if let Some(val) = conf_get("app-layer.protocols.snmp.response-timeout") {
+        match val.parse::<u64>() {
+            Ok(v) => SNMP_RESPONSE_TIMEOUT = v,
+            _     => { SCLogError!("Invalid value for snmp.response-timeout: {}", val); },
+        }
+    }
+    mib::snmp_mib_parse_config();
+    #[cfg(feature = "snmp-decrypt")]
+    usm::snmp_usm_parse_config();
+}
+
+// Register the parser for an IP protocol, on ports 161 (requests) and 162
+// (notifications)
+unsafe fn register_snmp_parser(ipproto: i32, probe: ProbeFn, parse_ts: ParseFn, parse_tc: ParseFn) {
     let default_port = CString::new("161").unwrap();
     let mut parser = RustParser {
         name               : PARSER_NAME.as_ptr() as *const std::os::raw::c_char,
         default_port       : default_port.as_ptr(),
-        ipproto            : core::IPPROTO_UDP,
-        probe_ts           : Some(rs_snmp_probing_parser),
-        probe_tc           : Some(rs_snmp_probing_parser),
+        ipproto            : ipproto,
+        probe_ts           : Some(probe),
+        probe_tc           : Some(probe),
         min_depth          : 0,
         max_depth          : 16,
         state_new          : rs_snmp_state_new,
         state_free         : rs_snmp_state_free,
         tx_free            : rs_snmp_state_tx_free,
-        parse_ts           : rs_snmp_parse_request,
-        parse_tc           : rs_snmp_parse_response,
+        parse_ts           : parse_ts,
+        parse_tc           : parse_tc,
         get_tx_count       : rs_snmp_state_get_tx_count,
         get_tx             : rs_snmp_state_get_tx,
         tx_comp_st_ts      : 1,
@@ -517,10 +1408,11 @@ pub unsafe extern "C" fn rs_register_snmp_parser() {
         get_tx_iterator    : None,
         get_tx_data        : rs_snmp_get_tx_data,
         apply_tx_config    : None,
-        flags              : APP_LAYER_PARSER_OPT_UNIDIR_TXS,
+        flags              : 0,
         truncate           : None,
     };
-    let ip_proto_str = CString::new("udp").unwrap();
+    let proto_name = if ipproto == core::IPPROTO_TCP { "tcp" } else { "udp" };
+    let ip_proto_str = CString::new(proto_name).unwrap();
     if AppLayerProtoDetectConfProtoDetectionEnabled(ip_proto_str.as_ptr(), parser.name) != 0 {
         // port 161
         let alproto = AppLayerRegisterProtocolDetection(&parser, 1);
@@ -529,7 +1421,7 @@ pub unsafe extern "C" fn rs_register_snmp_parser() {
         if AppLayerParserConfParserEnabled(ip_proto_str.as_ptr(), parser.name) != 0 {
             let _ = AppLayerRegisterParser(&parser, alproto);
         }
-        AppLayerParserRegisterGetTxIterator(core::IPPROTO_UDP as u8, alproto, rs_snmp_get_tx_iterator);
+        AppLayerParserRegisterGetTxIterator(ipproto as u8, alproto, rs_snmp_get_tx_iterator);
         // port 162
         let default_port_traps = CString::new("162").unwrap();
         parser.default_port = default_port_traps.as_ptr();
@@ -538,6 +1430,620 @@ pub unsafe extern "C" fn rs_register_snmp_parser() {
             let _ = AppLayerRegisterParser(&parser, alproto);
         }
     } else {
-        SCLogDebug!("Protocol detector and parser disabled for SNMP.");
+        SCLogDebug!("Protocol detector and parser disabled for SNMP over {}.", proto_name);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_register_snmp_parser() {
+    register_snmp_parser(core::IPPROTO_UDP, rs_snmp_probing_parser,
+                         rs_snmp_parse_request, rs_snmp_parse_response);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_register_snmp_tcp_parser() {
+    register_snmp_parser(core::IPPROTO_TCP, rs_snmp_probing_parser_tcp,
+                         rs_snmp_parse_request_tcp, rs_snmp_parse_response_tcp);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snmp::detect::{rs_snmp_tx_get_error_status,rs_snmp_tx_get_vars_count};
+    use crate::snmp::test_data::*;
+
+    #[test]
+    fn test_snmp_message_length() {
+        assert_eq!(parse_snmp_message_length(SNMPV2C_REQ).map(|(_,l)| l), Ok(41));
+        let long: &[u8] = &[0x30, 0x82, 0x01, 0x00, 0x02];
+        assert_eq!(parse_snmp_message_length(long).map(|(rem,l)| (rem.len(),l)), Ok((1, 260)));
+        let short: &[u8] = &[0x30, 0x84, 0x00];
+        assert!(matches!(parse_snmp_message_length(short), Err(nom::Err::Incomplete(_))));
+        let too_long: &[u8] = &[0x30, 0x85, 0x00, 0x00, 0x00, 0x00, 0x01];
+        assert!(matches!(parse_snmp_message_length(too_long), Err(nom::Err::Error(_))));
+        let huge: &[u8] = &[0x30, 0x84, 0xff, 0xff, 0xff, 0xff];
+        assert!(matches!(parse_snmp_message_length(huge), Err(nom::Err::Error(_))));
+    }
+
+    #[test]
+    fn test_snmp_probe_envelope() {
+        assert_eq!(2, parse_pdu_enveloppe_version(SNMPV2C_REQ).unwrap().1);
+        assert_eq!(1, parse_pdu_enveloppe_version(SNMPV1_TRAP).unwrap().1);
+        assert_eq!(3, parse_pdu_enveloppe_version(SNMPV3_REQ).unwrap().1);
+        assert!(parse_pdu_enveloppe_version(NOT_SNMP).is_err());
+        match parse_pdu_enveloppe_version(&SNMPV2C_REQ[..20]) {
+            Err(nom::Err::Incomplete(_)) => (),
+            r                            => panic!("unexpected result {:?}", r),
+        }
+
+        // invalid msgMaxSize
+        let mut buf = SNMPV3_REQ.to_vec();
+        buf[13..16].copy_from_slice(&[0x00, 0x00, 0x10]);
+        assert!(parse_pdu_enveloppe_version(&buf).is_err());
+
+        // invalid msgSecurityModel
+        let mut buf = SNMPV3_REQ.to_vec();
+        buf[21] = 0;
+        assert!(parse_pdu_enveloppe_version(&buf).is_err());
+    }
+
+    #[test]
+    fn test_snmp_parse_tcp_incomplete() {
+        let mut state = SNMPState::new();
+        assert_eq!(AppLayerResult::incomplete(0, 41),
+                   state.parse_tcp(&SNMPV2C_REQ[..20], STREAM_TOSERVER));
+        assert_eq!(AppLayerResult::incomplete(0, 2),
+                   state.parse_tcp(&SNMPV2C_REQ[..1], STREAM_TOSERVER));
+        assert_eq!(0, state.transactions.len());
+        assert_eq!(AppLayerResult::ok(), state.parse_tcp(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(1, state.transactions.len());
+    }
+
+    #[test]
+    fn test_snmp_parse_tcp_too_large() {
+        // 100000 bytes announced, above the default maximum message size
+        let buf: &[u8] = &[0x30, 0x83, 0x01, 0x86, 0x9b, 0x02, 0x01, 0x01];
+        let mut state = SNMPState::new();
+        assert_eq!(AppLayerResult::err(), state.parse_tcp(buf, STREAM_TOSERVER));
+        state.max_message_size = 200000;
+        assert_eq!(AppLayerResult::incomplete(0, 100000), state.parse_tcp(buf, STREAM_TOSERVER));
+    }
+
+    #[test]
+    fn test_snmp_parse_tcp_multiple() {
+        let mut buf = SNMPV2C_REQ.to_vec();
+        buf.extend_from_slice(SNMPV2C_REQ);
+        buf.extend_from_slice(&SNMPV2C_REQ[..4]);
+        let mut state = SNMPState::new();
+        assert_eq!(AppLayerResult::incomplete(82, 41),
+                   state.parse_tcp(&buf, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions.len());
+        assert_eq!(Some("[R0_C@cti!]".to_string()), state.transactions[1].community);
+    }
+
+    #[test]
+    fn test_snmp_parse_udp_multiple() {
+        let mut buf = SNMPV2C_REQ.to_vec();
+        buf.extend_from_slice(SNMPV2C_REQ);
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(&buf, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions.len());
+        assert_eq!(Some("[R0_C@cti!]".to_string()), state.transactions[1].community);
+
+        // a trailing remnant does not discard the parsed messages
+        buf.extend_from_slice(&SNMPV2C_REQ[..4]);
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(&buf, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions.len());
+
+        let mut state = SNMPState::new();
+        assert_eq!(-1, state.parse(&SNMPV2C_REQ[..4], STREAM_TOSERVER));
+        assert_eq!(0, state.transactions.len());
+    }
+
+    #[test]
+    fn test_snmp_remnant_event() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(0, state.parse(SNMPV1_TRAP, STREAM_TOSERVER));
+
+        // the response is paired with the request, which is not the most
+        // recent transaction: the event is set on the request
+        let mut buf = SNMPV2C_RESP.to_vec();
+        buf.extend_from_slice(&[0x02, 0x01, 0x00]);
+        assert_eq!(0, state.parse(&buf, STREAM_TOCLIENT));
+        assert_eq!(2, state.transactions.len());
+        assert!(state.transactions[0].response_info.is_some());
+        assert!(state.transactions[0].has_event(SNMPEvent::MalformedData));
+        assert!(!state.transactions[1].has_event(SNMPEvent::MalformedData));
+    }
+
+    #[test]
+    fn test_snmp_truncated_pdu() {
+        let truncated = &SNMPV2C_REQ[..20];
+        let mut state = SNMPState::new();
+        assert_eq!(-1, state.parse(truncated, STREAM_TOSERVER));
+        assert_eq!(0, state.transactions.len());
+
+        // the event is set on the message preceding the truncated one
+        let mut buf = SNMPV2C_REQ.to_vec();
+        buf.extend_from_slice(truncated);
+        assert_eq!(0, state.parse(&buf, STREAM_TOSERVER));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].has_event(SNMPEvent::TruncatedPdu));
+        assert!(!state.transactions[0].has_event(SNMPEvent::MalformedData));
+
+        // without a message parsed in the same datagram, the error is
+        // returned
+        assert_eq!(-1, state.parse(truncated, STREAM_TOSERVER));
+        assert_eq!(1, state.transactions.len());
+    }
+
+    #[test]
+    fn test_snmp_inner_length_overrun() {
+        // complete message, with a varbind list longer than the PDU
+        let mut buf = SNMPV2C_REQ.to_vec();
+        buf[33] = 0x09;
+        let mut state = SNMPState::new();
+        assert_eq!(-1, state.parse(&buf, STREAM_TOSERVER));
+
+        buf.splice(0..0, SNMPV2C_REQ.iter().cloned());
+        assert_eq!(0, state.parse(&buf, STREAM_TOSERVER));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].has_event(SNMPEvent::MalformedData));
+        assert!(!state.transactions[0].has_event(SNMPEvent::TruncatedPdu));
+    }
+
+    #[test]
+    fn test_snmp_error_status_names() {
+        assert_eq!(Some("noError"), snmp_error_status_name(0));
+        assert_eq!(Some("authorizationError"), snmp_error_status_name(16));
+        assert_eq!(None, snmp_error_status_name(19));
+        assert_eq!(Some(2), snmp_error_status_from_name("nosuchname"));
+        assert_eq!(Some(18), snmp_error_status_from_name("inconsistentName"));
+        assert_eq!(None, snmp_error_status_from_name("no_such_name"));
+    }
+
+    #[test]
+    fn test_snmp_pair_request_response() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(1, state.transactions.len());
+        assert!(!state.transactions[0].is_complete());
+        assert_eq!(0x2b133f85, state.transactions[0].info.as_ref().unwrap().request_id);
+
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].is_complete());
+        let resp = state.transactions[0].response_info.as_ref().unwrap();
+        assert_eq!(PduType::Response, resp.pdu_type);
+        assert_eq!(0x2b133f85, resp.request_id);
+
+        // a response without a pending request gets its own transaction
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        assert_eq!(2, state.transactions.len());
+        assert!(state.transactions[1].is_complete());
+        assert!(state.transactions[1].response_info.is_none());
+
+        // a response in the direction of the request is not paired
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions.len());
+        assert!(!state.transactions[0].is_complete());
+    }
+
+    #[test]
+    fn test_snmp_response_timeout() {
+        let mut state = SNMPState::new();
+        state.response_timeout = 5;
+        state.update_ts(100);
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        state.update_ts(102);
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        state.update_ts(104);
+        assert!(!state.transactions[0].is_complete());
+
+        // only the first request timed out
+        state.update_ts(105);
+        assert!(state.transactions[0].is_complete());
+        assert!(!state.transactions[1].is_complete());
+
+        // a late response is paired with the request still waiting
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        assert_eq!(2, state.transactions.len());
+        assert!(state.transactions[0].response_info.is_none());
+        assert!(state.transactions[1].response_info.is_some());
+
+        // disabled
+        let mut state = SNMPState::new();
+        state.response_timeout = 0;
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        state.update_ts(1000000);
+        assert!(!state.transactions[0].is_complete());
+    }
+
+    #[test]
+    fn test_snmp_error_status() {
+        let mut error = -1;
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        // an unanswered request has no error-status
+        assert!(!unsafe { rs_snmp_tx_get_error_status(&mut state.transactions[0], &mut error) });
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        assert!(unsafe { rs_snmp_tx_get_error_status(&mut state.transactions[0], &mut error) });
+        assert_eq!(0, error);
+
+        // a response without a request has its own error-status
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_RESP_VALUES, STREAM_TOCLIENT));
+        assert!(unsafe { rs_snmp_tx_get_error_status(&mut state.transactions[0], &mut error) });
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV1_TRAP, STREAM_TOSERVER));
+        assert!(!unsafe { rs_snmp_tx_get_error_status(&mut state.transactions[0], &mut error) });
+    }
+
+    #[test]
+    fn test_snmp_varbind_values() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_RESP_VALUES, STREAM_TOCLIENT));
+        let vars : Vec<(Oid, &SnmpValue)> = state.transactions[0].varbinds().collect();
+        assert_eq!(2, vars.len());
+        assert_eq!("1.3.6.1.2.1.1.1.0", vars[0].0.to_string());
+        assert_eq!(&SnmpValue::OctetString(b"Linux".to_vec()), vars[0].1);
+        assert_eq!("1.3.6.1.2.1.1.3.0", vars[1].0.to_string());
+        assert_eq!(&SnmpValue::TimeTicks(256), vars[1].1);
+        let tx = &mut state.transactions[0];
+        assert_eq!(Some("1.3.6.1.2.1.1.3.0"), tx.oid_str(1));
+        assert_eq!(Some("1.3.6.1.2.1.1.1.0"), tx.oid_str(0));
+        assert_eq!(None, tx.oid_str(2));
+    }
+
+    #[test]
+    fn test_snmp_ber_integer_value() {
+        let (_, obj) = parse_ber_integer(&[0x02, 0x02, 0xff, 0x00]).unwrap();
+        assert_eq!(Some(-256), ber_integer_value(&obj));
+        let (_, obj) = parse_ber_integer(&[0x02, 0x02, 0x01, 0x00]).unwrap();
+        assert_eq!(Some(256), ber_integer_value(&obj));
+    }
+
+    #[test]
+    fn test_snmp_v3_engine_id() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        let tx = &state.transactions[0];
+        assert_eq!(3, tx.version);
+        assert_eq!(Some("admin".to_string()), tx.usm);
+        assert_eq!(Some(vec![0x80, 0x00, 0x1f, 0x88, 0x80, 0x59, 0xdc, 0x48,
+                             0x61, 0x45, 0xa2, 0x63, 0x22]), tx.engine_id);
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(None, state.transactions[0].engine_id);
+    }
+
+    #[test]
+    fn test_snmp_v3_security_flags() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        let flags = state.transactions[0].security_flags.unwrap();
+        assert_eq!(SNMPSecurityFlags::REPORTABLE, flags);
+        assert!(!flags.contains(SNMPSecurityFlags::AUTH));
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(None, state.transactions[0].security_flags);
+    }
+
+    #[test]
+    fn test_snmp_v1_trap() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV1_TRAP, STREAM_TOSERVER));
+        let info = state.transactions[0].info.as_ref().unwrap();
+        let trap = info.trap_type.as_ref().unwrap();
+        assert_eq!(TrapType::ENTERPRISE_SPECIFIC, trap.generic_trap);
+        assert_eq!(Some(17), trap.specific_code());
+        assert_eq!(1000, trap.timestamp);
+        assert_eq!("1.3.6.1.4.1.8072.2.3.1", trap.enterprise.to_string());
+        assert!(state.transactions[0].is_complete());
+
+        // same trap, as linkDown
+        let mut buf = SNMPV1_TRAP.to_vec();
+        buf[35] = 2;
+        buf[38] = 0;
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(&buf, STREAM_TOSERVER));
+        let info = state.transactions[0].info.as_ref().unwrap();
+        let trap = info.trap_type.as_ref().unwrap();
+        assert_eq!(TrapType::LINK_DOWN, trap.generic_trap);
+        assert_eq!(None, trap.specific_code());
+        assert_eq!(1000, trap.timestamp);
+    }
+
+    #[test]
+    fn test_snmp_max_tx() {
+        let mut state = SNMPState::new();
+        state.max_tx = 4;
+        assert_eq!(0, state.parse(SNMPV1_TRAP, STREAM_TOSERVER));
+        for _ in 0..5 {
+            assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+            assert!(state.transactions.len() <= 4);
+        }
+        assert_eq!(6, state.tx_id);
+        // the trap was complete and was dropped first, then the last request
+        // was refused as the oldest one is still unanswered
+        let ids : Vec<u64> = state.transactions.iter().map(|tx| tx.id).collect();
+        assert_eq!(vec![2, 3, 4, 5], ids);
+        assert!(state.get_tx_by_id(0).is_none());
+        assert!(!state.transactions[0].is_complete());
+        assert!(!state.transactions[2].has_event(SNMPEvent::TooManyTransactions));
+        assert!(state.transactions[3].has_event(SNMPEvent::TooManyTransactions));
+
+        // once answered, a request can be dropped
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        assert!(state.transactions[3].response_info.is_some());
+        assert_eq!(0, state.parse(SNMPV1_TRAP, STREAM_TOSERVER));
+        let ids : Vec<u64> = state.transactions.iter().map(|tx| tx.id).collect();
+        assert_eq!(vec![2, 3, 4, 7], ids);
+        assert!(state.transactions[3].has_event(SNMPEvent::TooManyTransactions));
+    }
+
+    #[test]
+    fn test_snmp_getbulk() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_GETBULK, STREAM_TOSERVER));
+        let info = state.transactions[0].info.as_ref().unwrap();
+        assert_eq!(PduType::GetBulkRequest, info.pdu_type);
+        assert_eq!(Some(SnmpBulkInfo{ non_repeaters: 0, max_repetitions: 5000 }), info.bulk);
+        assert!(state.is_excessive_bulk(info));
+        assert!(state.transactions[0].has_event(SNMPEvent::ExcessiveBulkRepetitions));
+
+        // at the threshold
+        let mut state = SNMPState::new();
+        state.max_bulk_repetitions = 5000;
+        assert_eq!(0, state.parse(SNMPV2C_GETBULK, STREAM_TOSERVER));
+        assert!(!state.transactions[0].has_event(SNMPEvent::ExcessiveBulkRepetitions));
+        assert!(!state.is_excessive_bulk(state.transactions[0].info.as_ref().unwrap()));
+        state.max_bulk_repetitions = 0;
+        assert!(!state.is_excessive_bulk(state.transactions[0].info.as_ref().unwrap()));
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(None, state.transactions[0].info.as_ref().unwrap().bulk);
+    }
+
+    #[cfg(feature = "snmp-decrypt")]
+    #[test]
+    fn test_snmp_scoped_pdu_info() {
+        // decrypted scoped PDU, with DES padding
+        let mut scoped = SCOPED_PDU.to_vec();
+        scoped.push(0);
+        let info = SNMPState::get_scoped_pdu_info(&scoped).unwrap();
+        assert_eq!(PduType::GetRequest, info.pdu_type);
+        assert_eq!(0x1237, info.request_id);
+        assert_eq!(1, info.vars.len());
+        assert_eq!("1.3.6.1.2.1.1.1.0", info.varbinds().next().unwrap().0.to_string());
+
+        assert!(SNMPState::get_scoped_pdu_info(&scoped[..20]).is_none());
+        assert_eq!(vec![0x30, 0x81, 0x80], ber_sequence_header(128));
+    }
+
+    #[test]
+    fn test_snmp_tx_direction() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        let tx = &mut state.transactions[0] as *mut SNMPTransaction as *mut std::os::raw::c_void;
+        assert_eq!(STREAM_TOSERVER, state.transactions[0].direction);
+        unsafe {
+            assert_eq!(1, rs_snmp_tx_get_alstate_progress(tx, STREAM_TOSERVER));
+            assert_eq!(0, rs_snmp_tx_get_alstate_progress(tx, STREAM_TOCLIENT));
+        }
+
+        // the paired response completes the request, which keeps its direction
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        let tx = &mut state.transactions[0] as *mut SNMPTransaction as *mut std::os::raw::c_void;
+        assert_eq!(STREAM_TOSERVER, state.transactions[0].direction);
+        unsafe {
+            assert_eq!(1, rs_snmp_tx_get_alstate_progress(tx, STREAM_TOCLIENT));
+        }
+
+        // a response without a request creates a transaction in the response direction
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_RESP, STREAM_TOCLIENT));
+        assert_eq!(STREAM_TOCLIENT, state.transactions[0].direction);
+        let tx = &mut state.transactions[0] as *mut SNMPTransaction as *mut std::os::raw::c_void;
+        unsafe {
+            assert_eq!(1, rs_snmp_tx_get_alstate_progress(tx, STREAM_TOCLIENT));
+            assert_eq!(1, rs_snmp_tx_get_alstate_progress(tx, STREAM_TOSERVER));
+        }
+    }
+
+    #[test]
+    fn test_snmp_get_tx_by_id() {
+        let mut state = SNMPState::new();
+        state.max_tx = 10000;
+        for _ in 0..5000 {
+            assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        }
+        assert_eq!(5000, state.transactions.len());
+        // free some transactions, leaving holes
+        for tx_id in (0..5000).step_by(3) {
+            state.free_tx(tx_id);
+        }
+        for tx_id in 0..5000 {
+            match state.get_tx_by_id(tx_id) {
+                Some(tx) => {
+                    assert!(tx_id % 3 != 0);
+                    assert_eq!(tx_id + 1, tx.id);
+                },
+                None     => assert_eq!(0, tx_id % 3),
+            }
+        }
+        assert!(state.get_tx_by_id(5000).is_none());
+    }
+
+    #[test]
+    fn test_snmp_v3_msg_id() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert_eq!(Some(0x1234), state.transactions[0].msg_id);
+        assert_eq!(Some(65507), state.transactions[0].msg_max_size);
+        assert!(!state.transactions[0].is_complete());
+
+        // paired on msgID, even if the request-id differs
+        assert_eq!(0, state.parse(SNMPV3_RESP, STREAM_TOCLIENT));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].is_complete());
+        assert_eq!(1, state.transactions[0].response_info.as_ref().unwrap().request_id);
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(None, state.transactions[0].msg_id);
+        assert_eq!(None, state.transactions[0].msg_max_size);
+    }
+
+    #[test]
+    fn test_snmp_v3_encrypted_pairing() {
+        // same msgID, without the reportable flag
+        let mut resp = SNMPV3_ENC_REQ.to_vec();
+        resp[18] = 0x03;
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_ENC_REQ, STREAM_TOSERVER));
+        assert!(state.transactions[0].encrypted);
+        assert!(!state.transactions[0].is_complete());
+
+        assert_eq!(0, state.parse(&resp, STREAM_TOCLIENT));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].response_encrypted);
+        assert!(state.transactions[0].is_complete());
+
+        // a message in the same direction is not a response
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_ENC_REQ, STREAM_TOSERVER));
+        assert_eq!(0, state.parse(&resp, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions.len());
+    }
+
+    #[test]
+    fn test_snmp_v3_encrypted_trap() {
+        // GetRequest without the reportable flag, msgID 0x1234
+        let mut req = SNMPV3_REQ.to_vec();
+        req[18] = 0x00;
+        // encrypted and not reportable (trap), same msgID
+        let mut trap = SNMPV3_ENC_REQ.to_vec();
+        trap[9] = 0x12;
+        trap[10] = 0x34;
+        trap[18] = 0x03;
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(&req, STREAM_TOSERVER));
+        assert_eq!(0, state.parse(&trap, STREAM_TOCLIENT));
+        assert_eq!(2, state.transactions.len());
+        assert!(!state.transactions[0].has_response());
+        assert!(state.transactions[1].encrypted);
+        assert_eq!(Some(0x1234), state.transactions[1].msg_id);
+
+        // with a reportable request, it is assumed to be the response
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert_eq!(0, state.parse(&trap, STREAM_TOCLIENT));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].response_encrypted);
+    }
+
+    #[test]
+    fn test_snmp_mixed_versions() {
+        // an agent falling back from SNMPv3 to SNMPv2c
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions.len());
+        assert_eq!(3, state.transactions[0].version);
+        assert_eq!(2, state.transactions[1].version);
+        assert!(!state.transactions[0].has_event(SNMPEvent::VersionMismatch));
+        assert!(!state.transactions[1].has_event(SNMPEvent::VersionMismatch));
+
+        // a SNMPv1 trap PDU in a SNMPv2c message
+        let mut buf = SNMPV1_TRAP.to_vec();
+        buf[4] = 0x01;
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(&buf, STREAM_TOSERVER));
+        assert_eq!(2, state.transactions[0].version);
+        assert_eq!(PduType::TrapV1, state.transactions[0].info.as_ref().unwrap().pdu_type);
+        assert!(state.transactions[0].has_event(SNMPEvent::VersionMismatch));
+    }
+
+    #[test]
+    fn test_snmp_pdu_version() {
+        let mut info = SNMPPduInfo::default();
+        info.pdu_type = PduType::GetBulkRequest;
+        assert!(!info.is_valid_for_version(1));
+        assert!(info.is_valid_for_version(2));
+        assert!(info.is_valid_for_version(3));
+        info.pdu_type = PduType::TrapV1;
+        assert!(info.is_valid_for_version(1));
+        assert!(!info.is_valid_for_version(2));
+        assert!(!info.is_valid_for_version(3));
+        info.pdu_type = PduType::GetRequest;
+        assert!(info.is_valid_for_version(1));
+        assert!(info.is_valid_for_version(3));
+    }
+
+    #[test]
+    fn test_snmp_v3_security_model() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert_eq!(Some(3), state.transactions[0].security_model);
+        assert!(!state.community_seen);
+        assert!(!state.transactions[0].has_event(SNMPEvent::UnauthenticatedV3InCommunityFlow));
+
+        // noAuthNoPriv SNMPv3 message after a SNMPv2c one
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV2C_REQ, STREAM_TOSERVER));
+        assert_eq!(None, state.transactions[0].security_model);
+        assert!(state.community_seen);
+        assert!(!state.transactions[0].has_event(SNMPEvent::UnauthenticatedV3InCommunityFlow));
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert_eq!(Some(3), state.transactions[1].security_model);
+        assert!(state.transactions[1].has_event(SNMPEvent::UnauthenticatedV3InCommunityFlow));
+    }
+
+    #[test]
+    fn test_snmp_vars_count() {
+        let mut count = 0;
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert!(unsafe { rs_snmp_tx_get_vars_count(&mut state.transactions[0], &mut count) });
+        assert_eq!(1, count);
+        assert_eq!(0, state.parse(SNMPV3_RESP, STREAM_TOCLIENT));
+        assert!(unsafe { rs_snmp_tx_get_vars_count(&mut state.transactions[0], &mut count) });
+        assert_eq!(2, count);
+
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_ENC_REQ, STREAM_TOSERVER));
+        assert!(!unsafe { rs_snmp_tx_get_vars_count(&mut state.transactions[0], &mut count) });
+    }
+
+    #[test]
+    fn test_snmp_v3_report() {
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REQ, STREAM_TOSERVER));
+        assert!(!state.transactions[0].report);
+        assert_eq!(0, state.parse(SNMPV3_REPORT, STREAM_TOCLIENT));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].report);
+        assert!(state.transactions[0].has_event(SNMPEvent::Report));
+        assert!(state.transactions[0].is_complete());
+        assert_eq!(PduType::Report, state.transactions[0].response_info.as_ref().unwrap().pdu_type);
+
+        // without the request
+        let mut state = SNMPState::new();
+        assert_eq!(0, state.parse(SNMPV3_REPORT, STREAM_TOCLIENT));
+        assert_eq!(1, state.transactions.len());
+        assert!(state.transactions[0].report);
+        assert!(state.transactions[0].has_event(SNMPEvent::Report));
+        assert!(state.transactions[0].response_info.is_none());
     }
 }
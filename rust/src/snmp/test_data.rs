@@ -0,0 +1,159 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+//! SNMP messages shared by the tests of the parser, logger and detection.
+
+// SNMPv2c GetNextRequest, community "[R0_C@cti!]"
+pub const SNMPV2C_REQ : &[u8] = &[
+    0x30, 0x27, 0x02, 0x01, 0x01, 0x04, 0x0b, 0x5b,
+    0x52, 0x30, 0x5f, 0x43, 0x40, 0x63, 0x74, 0x69,
+    0x21, 0x5d, 0xa1, 0x15, 0x02, 0x04, 0x2b, 0x13,
+    0x3f, 0x85, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00,
+    0x30, 0x07, 0x30, 0x05, 0x06, 0x01, 0x01, 0x05,
+    0x00
+];
+
+// SNMPv2c Response to SNMPV2C_REQ
+pub const SNMPV2C_RESP : &[u8] = &[
+    0x30, 0x27, 0x02, 0x01, 0x01, 0x04, 0x0b, 0x5b,
+    0x52, 0x30, 0x5f, 0x43, 0x40, 0x63, 0x74, 0x69,
+    0x21, 0x5d, 0xa2, 0x15, 0x02, 0x04, 0x2b, 0x13,
+    0x3f, 0x85, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00,
+    0x30, 0x07, 0x30, 0x05, 0x06, 0x01, 0x01, 0x05,
+    0x00
+];
+
+// SNMPv2c Response, community "public", with sysDescr.0 = "Linux" and
+// sysUpTime.0 = 256
+pub const SNMPV2C_RESP_VALUES : &[u8] = &[
+    0x30, 0x3b, 0x02, 0x01, 0x01, 0x04, 0x06, 0x70,
+    0x75, 0x62, 0x6c, 0x69, 0x63, 0xa2, 0x2e, 0x02,
+    0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00,
+    0x30, 0x23, 0x30, 0x11, 0x06, 0x08, 0x2b, 0x06,
+    0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x04, 0x05,
+    0x4c, 0x69, 0x6e, 0x75, 0x78, 0x30, 0x0e, 0x06,
+    0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03,
+    0x00, 0x43, 0x02, 0x01, 0x00
+];
+
+// SNMPv3 GetRequest, noAuthNoPriv, user "admin", msgID 0x1234
+pub const SNMPV3_REQ : &[u8] = &[
+    0x30, 0x6a, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+    0x02, 0x12, 0x34, 0x02, 0x03, 0x00, 0xff, 0xe3,
+    0x04, 0x01, 0x04, 0x02, 0x01, 0x03, 0x04, 0x23,
+    0x30, 0x21, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+    0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+    0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+    0x04, 0x05, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x04,
+    0x00, 0x04, 0x00, 0x30, 0x2f, 0x04, 0x0d, 0x80,
+    0x00, 0x1f, 0x88, 0x80, 0x59, 0xdc, 0x48, 0x61,
+    0x45, 0xa2, 0x63, 0x22, 0x04, 0x00, 0xa0, 0x1c,
+    0x02, 0x04, 0x0a, 0x0b, 0x0c, 0x0d, 0x02, 0x01,
+    0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c,
+    0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01,
+    0x01, 0x00, 0x05, 0x00
+];
+
+// SNMPv1 enterprise specific trap 17, from 192.168.1.1
+pub const SNMPV1_TRAP : &[u8] = &[
+    0x30, 0x3c, 0x02, 0x01, 0x00, 0x04, 0x06, 0x70,
+    0x75, 0x62, 0x6c, 0x69, 0x63, 0xa4, 0x2f, 0x06,
+    0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0xbf, 0x08,
+    0x02, 0x03, 0x01, 0x40, 0x04, 0xc0, 0xa8, 0x01,
+    0x01, 0x02, 0x01, 0x06, 0x02, 0x01, 0x11, 0x43,
+    0x02, 0x03, 0xe8, 0x30, 0x11, 0x30, 0x0f, 0x06,
+    0x0a, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x02, 0x02,
+    0x01, 0x01, 0x02, 0x02, 0x01, 0x02
+];
+
+// SNMPv2c GetBulkRequest, community "public", non-repeaters 0, max-repetitions 5000
+pub const SNMPV2C_GETBULK : &[u8] = &[
+    0x30, 0x25, 0x02, 0x01, 0x01, 0x04, 0x06, 0x70,
+    0x75, 0x62, 0x6c, 0x69, 0x63, 0xa5, 0x18, 0x02,
+    0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x02, 0x13,
+    0x88, 0x30, 0x0c, 0x30, 0x0a, 0x06, 0x06, 0x2b,
+    0x06, 0x01, 0x02, 0x01, 0x01, 0x05, 0x00
+];
+
+// SEQUENCE { 1, 5, SEQUENCE { 2, "" } }, similar to the SNMP envelope
+pub const NOT_SNMP : &[u8] = &[
+    0x30, 0x0d, 0x02, 0x01, 0x01, 0x02, 0x01, 0x05,
+    0x30, 0x05, 0x02, 0x01, 0x02, 0x04, 0x00
+];
+
+// SNMPv3 Response to SNMPV3_REQ (same msgID), with another request-id
+pub const SNMPV3_RESP : &[u8] = &[
+    0x30, 0x6c, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+    0x02, 0x12, 0x34, 0x02, 0x03, 0x00, 0xff, 0xe3,
+    0x04, 0x01, 0x00, 0x02, 0x01, 0x03, 0x04, 0x23,
+    0x30, 0x21, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+    0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+    0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+    0x04, 0x05, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x04,
+    0x00, 0x04, 0x00, 0x30, 0x31, 0x04, 0x0d, 0x80,
+    0x00, 0x1f, 0x88, 0x80, 0x59, 0xdc, 0x48, 0x61,
+    0x45, 0xa2, 0x63, 0x22, 0x04, 0x00, 0xa2, 0x1e,
+    0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01,
+    0x00, 0x30, 0x13, 0x30, 0x11, 0x06, 0x08, 0x2b,
+    0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x04,
+    0x05, 0x4c, 0x69, 0x6e, 0x75, 0x78
+];
+
+// SNMPv3 encrypted message, authPriv and reportable (request)
+pub const SNMPV3_ENC_REQ : &[u8] = &[
+    0x30, 0x5f, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+    0x02, 0x20, 0x00, 0x02, 0x03, 0x00, 0xff, 0xe3,
+    0x04, 0x01, 0x07, 0x02, 0x01, 0x03, 0x04, 0x37,
+    0x30, 0x35, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+    0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+    0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+    0x04, 0x05, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x04,
+    0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x08, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+    0x10, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+    0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+    0x0f
+];
+
+// SNMPv3 Report to SNMPV3_REQ, usmStatsUnknownUserNames.0 = 1
+pub const SNMPV3_REPORT : &[u8] = &[
+    0x30, 0x6d, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+    0x02, 0x12, 0x34, 0x02, 0x03, 0x00, 0xff, 0xe3,
+    0x04, 0x01, 0x00, 0x02, 0x01, 0x03, 0x04, 0x23,
+    0x30, 0x21, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+    0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+    0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+    0x04, 0x05, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x04,
+    0x00, 0x04, 0x00, 0x30, 0x32, 0x04, 0x0d, 0x80,
+    0x00, 0x1f, 0x88, 0x80, 0x59, 0xdc, 0x48, 0x61,
+    0x45, 0xa2, 0x63, 0x22, 0x04, 0x00, 0xa8, 0x1f,
+    0x02, 0x04, 0x0a, 0x0b, 0x0c, 0x0d, 0x02, 0x01,
+    0x00, 0x02, 0x01, 0x00, 0x30, 0x11, 0x30, 0x0f,
+    0x06, 0x0a, 0x2b, 0x06, 0x01, 0x06, 0x03, 0x0f,
+    0x01, 0x01, 0x03, 0x00, 0x41, 0x01, 0x01
+];
+
+// Decrypted scoped PDU of the SNMPv3 GetRequest used by the USM tests
+pub const SCOPED_PDU : &[u8] = &[
+    0x30, 0x2d, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+    0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+    0x22, 0x04, 0x00, 0xa0, 0x1a, 0x02, 0x02, 0x12,
+    0x37, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30,
+    0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01,
+    0x02, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00
+];
@@ -0,0 +1,323 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+//! Decryption of SNMPv3 scoped PDUs, using the User-based Security Model
+//! (RFC 3414 for DES, RFC 3826 for AES).
+//!
+//! Keys are configured per authoritative engine ID and user name. Messages
+//! are decrypted only: the authentication parameters are not verified.
+
+use crate::conf::conf_get;
+use crate::snmp::snmp_parser::UsmSecurityParameters;
+
+use aes::Aes128;
+use block_modes::{BlockMode, Cbc};
+use block_modes::block_padding::NoPadding;
+use cfb_mode::Cfb;
+use cfb_mode::cipher::{AsyncStreamCipher, NewCipher};
+use des::Des;
+use digest::Digest;
+use md5::Md5;
+use sha1::Sha1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UsmAuthProtocol {
+    Md5,
+    Sha,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UsmPrivProtocol {
+    Des,
+    Aes,
+}
+
+/// Privacy key of a user, localized for an authoritative engine
+pub struct UsmKey {
+    engine_id: Vec<u8>,
+    user: String,
+    priv_protocol: UsmPrivProtocol,
+    priv_key: Vec<u8>,
+}
+
+static mut SNMP_USM_KEYS: Vec<UsmKey> = Vec::new();
+
+// RFC 3414, section A.2
+fn password_to_key_with<D: Digest>(password: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    let mut buf = [0u8; 64];
+    let mut index = 0;
+    let mut count = 0;
+    while count < 1048576 {
+        for b in buf.iter_mut() {
+            *b = password[index % password.len()];
+            index += 1;
+        }
+        hasher.update(&buf);
+        count += buf.len();
+    }
+    hasher.finalize().to_vec()
+}
+
+// RFC 3414, section 2.6
+fn localize_key_with<D: Digest>(key: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    hasher.update(key);
+    hasher.update(engine_id);
+    hasher.update(key);
+    hasher.finalize().to_vec()
+}
+
+fn password_to_key(auth: UsmAuthProtocol, password: &[u8]) -> Vec<u8> {
+    match auth {
+        UsmAuthProtocol::Md5 => password_to_key_with::<Md5>(password),
+        UsmAuthProtocol::Sha => password_to_key_with::<Sha1>(password),
+    }
+}
+
+fn localize_key(auth: UsmAuthProtocol, key: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    match auth {
+        UsmAuthProtocol::Md5 => localize_key_with::<Md5>(key, engine_id),
+        UsmAuthProtocol::Sha => localize_key_with::<Sha1>(key, engine_id),
+    }
+}
+
+// RFC 3414, section 8.1.1.2
+fn decrypt_des(key: &[u8], salt: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    if key.len() < 16 || salt.len() != 8 || data.len() % 8 != 0 {
+        return None;
+    }
+    let iv : Vec<u8> = key[8..16].iter().zip(salt).map(|(k, s)| k ^ s).collect();
+    let cipher = Cbc::<Des, NoPadding>::new_from_slices(&key[..8], &iv).ok()?;
+    let mut buf = data.to_vec();
+    cipher.decrypt(&mut buf).ok()?;
+    Some(buf)
+}
+
+// RFC 3826, section 3.1.2.1
+fn decrypt_aes(key: &[u8], boots: u32, time: u32, salt: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    if key.len() < 16 || salt.len() != 8 {
+        return None;
+    }
+    let mut iv = Vec::with_capacity(16);
+    iv.extend_from_slice(&boots.to_be_bytes());
+    iv.extend_from_slice(&time.to_be_bytes());
+    iv.extend_from_slice(salt);
+    let mut cipher = Cfb::<Aes128>::new_from_slices(&key[..16], &iv).ok()?;
+    let mut buf = data.to_vec();
+    cipher.decrypt(&mut buf);
+    Some(buf)
+}
+
+impl UsmKey {
+    /// Derive the privacy key of a user from its password (at least 8
+    /// characters), and localize it for the engine.
+    pub fn new(engine_id: &[u8], user: &str, auth: UsmAuthProtocol,
+               priv_protocol: UsmPrivProtocol, priv_password: &[u8]) -> Option<UsmKey> {
+        if priv_password.len() < 8 {
+            return None;
+        }
+        let key = password_to_key(auth, priv_password);
+        Some(UsmKey {
+            engine_id: engine_id.to_vec(),
+            user: user.to_string(),
+            priv_protocol,
+            priv_key: localize_key(auth, &key, engine_id),
+        })
+    }
+
+    /// Decrypt a scoped PDU. The result may contain padding after the
+    /// scoped PDU.
+    pub fn decrypt(&self, usm: &UsmSecurityParameters, data: &[u8]) -> Option<Vec<u8>> {
+        match self.priv_protocol {
+            UsmPrivProtocol::Des => {
+                decrypt_des(&self.priv_key, usm.msg_privacy_parameters, data)
+            },
+            UsmPrivProtocol::Aes => {
+                decrypt_aes(&self.priv_key, usm.msg_authoritative_engine_boots,
+                            usm.msg_authoritative_engine_time,
+                            usm.msg_privacy_parameters, data)
+            },
+        }
+    }
+}
+
+/// Decrypt a scoped PDU, if a key is configured for the engine and user of
+/// the message.
+pub fn snmp_usm_decrypt(usm: &UsmSecurityParameters, data: &[u8]) -> Option<Vec<u8>> {
+    let keys = unsafe { &SNMP_USM_KEYS };
+    keys.iter()
+        .find(|k| k.engine_id == usm.msg_authoritative_engine_id && k.user == usm.msg_user_name)
+        .and_then(|k| k.decrypt(usm, data))
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len()).step_by(2)
+        .map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+/// Load the keys from the `app-layer.protocols.snmp.usm-keys` list
+pub unsafe fn snmp_usm_parse_config() {
+    SNMP_USM_KEYS.clear();
+    let mut index = 0;
+    loop {
+        let prefix = format!("app-layer.protocols.snmp.usm-keys.{}", index);
+        let user_key = format!("{}.user", prefix);
+        let user = match conf_get(&user_key) {
+            Some(user) => user,
+            None       => break,
+        };
+        index += 1;
+        let engine_id = match conf_get(&format!("{}.engine-id", prefix)).and_then(parse_hex) {
+            Some(engine_id) => engine_id,
+            None            => {
+                SCLogError!("Invalid or missing engine-id for SNMP user {}", user);
+                continue;
+            }
+        };
+        let auth = match conf_get(&format!("{}.auth-protocol", prefix)) {
+            Some("md5")                => UsmAuthProtocol::Md5,
+            Some("sha") | Some("sha1") => UsmAuthProtocol::Sha,
+            _                          => {
+                SCLogError!("Invalid or missing auth-protocol for SNMP user {}", user);
+                continue;
+            }
+        };
+        let priv_protocol = match conf_get(&format!("{}.priv-protocol", prefix)) {
+            Some("des") => UsmPrivProtocol::Des,
+            Some("aes") => UsmPrivProtocol::Aes,
+            _           => {
+                SCLogError!("Invalid or missing priv-protocol for SNMP user {}", user);
+                continue;
+            }
+        };
+        let password_key = format!("{}.priv-password", prefix);
+        let password = conf_get(&password_key).unwrap_or("");
+        match UsmKey::new(&engine_id, user, auth, priv_protocol, password.as_bytes()) {
+            Some(key) => SNMP_USM_KEYS.push(key),
+            None      => {
+                SCLogError!("Invalid priv-password for SNMP user {} (at least 8 characters)", user);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snmp::snmp_parser::{parse_snmp_v3,ScopedPduData,SecurityParameters};
+    use crate::snmp::test_data::SCOPED_PDU;
+
+    const ENGINE_ID : &[u8] = &[
+        0x80, 0x00, 0x1f, 0x88, 0x80, 0x59, 0xdc, 0x48,
+        0x61, 0x45, 0xa2, 0x63, 0x22
+    ];
+
+    // SNMPv3 GetRequest, authPriv (SHA/AES), user "maple", password "maplesyrup"
+    const SNMPV3_AES_REQ : &[u8] = &[
+        0x30, 0x7e, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+        0x02, 0x12, 0x37, 0x02, 0x03, 0x00, 0xff, 0xe3,
+        0x04, 0x01, 0x07, 0x02, 0x01, 0x03, 0x04, 0x37,
+        0x30, 0x35, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+        0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+        0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+        0x04, 0x05, 0x6d, 0x61, 0x70, 0x6c, 0x65, 0x04,
+        0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x08, 0x01,
+        0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x04,
+        0x2f, 0xff, 0x20, 0x68, 0x9c, 0x22, 0x46, 0x72,
+        0x2a, 0xc7, 0xc1, 0x06, 0x80, 0xfa, 0x4f, 0x2a,
+        0x7f, 0xd0, 0xd8, 0xdb, 0x95, 0x27, 0xbf, 0xa2,
+        0x63, 0xc2, 0x8d, 0x6b, 0xd0, 0x9c, 0xbd, 0x20,
+        0x75, 0x06, 0x10, 0xa2, 0xca, 0x8c, 0x6b, 0xe3,
+        0x93, 0xc1, 0xd1, 0x18, 0xa6, 0xd4, 0x0c, 0xb6
+    ];
+
+    // SNMPv3 GetRequest, authPriv (SHA/DES), user "maple", password "maplesyrup"
+    const SNMPV3_DES_REQ : &[u8] = &[
+        0x30, 0x7f, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+        0x02, 0x12, 0x38, 0x02, 0x03, 0x00, 0xff, 0xe3,
+        0x04, 0x01, 0x07, 0x02, 0x01, 0x03, 0x04, 0x37,
+        0x30, 0x35, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+        0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+        0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+        0x04, 0x05, 0x6d, 0x61, 0x70, 0x6c, 0x65, 0x04,
+        0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x08, 0x00,
+        0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x04,
+        0x30, 0x36, 0x6b, 0x14, 0xa7, 0x5d, 0x05, 0x55,
+        0x79, 0x06, 0x6b, 0xc1, 0x96, 0xcc, 0xee, 0x93,
+        0x39, 0x42, 0x13, 0x92, 0x53, 0x68, 0x46, 0x03,
+        0x61, 0x5f, 0x0a, 0xdb, 0x3f, 0xf4, 0xd8, 0x07,
+        0x84, 0xef, 0x54, 0x32, 0x27, 0x6f, 0x4a, 0x17,
+        0x52, 0x53, 0x1f, 0x33, 0xa3, 0x7e, 0x97, 0xec,
+        0x7b
+    ];
+
+    fn decrypt_message(key: &UsmKey, msg: &[u8]) -> Option<Vec<u8>> {
+        let (_, msg) = parse_snmp_v3(msg).unwrap();
+        match (&msg.security_params, &msg.data) {
+            (SecurityParameters::USM(usm), ScopedPduData::Encrypted(data)) => key.decrypt(usm, data),
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    // RFC 3414, sections A.3.1 and A.3.2
+    #[test]
+    fn test_snmp_usm_keys() {
+        let engine_id = parse_hex("000000000000000000000002").unwrap();
+        let key = password_to_key(UsmAuthProtocol::Md5, b"maplesyrup");
+        assert_eq!(parse_hex("9faf3283884e92834ebc9847d8edd963"), Some(key.clone()));
+        assert_eq!(parse_hex("526f5eed9fcce26f8964c2930787d82b"),
+                   Some(localize_key(UsmAuthProtocol::Md5, &key, &engine_id)));
+        let key = password_to_key(UsmAuthProtocol::Sha, b"maplesyrup");
+        assert_eq!(parse_hex("9fb5cc0381497b3793528939ff788d5d79145211"), Some(key.clone()));
+        assert_eq!(parse_hex("6695febc9288e36282235fc7151f128497b38f3f"),
+                   Some(localize_key(UsmAuthProtocol::Sha, &key, &engine_id)));
+    }
+
+    #[test]
+    fn test_snmp_usm_decrypt_aes() {
+        let key = UsmKey::new(ENGINE_ID, "maple", UsmAuthProtocol::Sha,
+                              UsmPrivProtocol::Aes, b"maplesyrup").unwrap();
+        assert_eq!(Some(SCOPED_PDU.to_vec()), decrypt_message(&key, SNMPV3_AES_REQ));
+
+        let key = UsmKey::new(ENGINE_ID, "maple", UsmAuthProtocol::Sha,
+                              UsmPrivProtocol::Aes, b"wrongpassword").unwrap();
+        assert_ne!(Some(SCOPED_PDU.to_vec()), decrypt_message(&key, SNMPV3_AES_REQ));
+    }
+
+    #[test]
+    fn test_snmp_usm_decrypt_des() {
+        let key = UsmKey::new(ENGINE_ID, "maple", UsmAuthProtocol::Sha,
+                              UsmPrivProtocol::Des, b"maplesyrup").unwrap();
+        let data = decrypt_message(&key, SNMPV3_DES_REQ).unwrap();
+        // the scoped PDU is padded to the block size
+        assert_eq!(48, data.len());
+        assert_eq!(SCOPED_PDU, &data[..SCOPED_PDU.len()]);
+    }
+
+    #[test]
+    fn test_snmp_usm_short_password() {
+        assert!(UsmKey::new(ENGINE_ID, "maple", UsmAuthProtocol::Md5,
+                            UsmPrivProtocol::Des, b"maple").is_none());
+    }
+}
@@ -38,6 +38,8 @@
 
 void RegisterSNMPParsers(void)
 {
+    rs_snmp_parse_config();
     rs_register_snmp_parser();
+    rs_register_snmp_tcp_parser();
 }
 
@@ -201,6 +201,9 @@
 #include "detect-snmp-version.h"
 #include "detect-snmp-community.h"
 #include "detect-snmp-pdu_type.h"
+#include "detect-snmp-error.h"
+#include "detect-snmp-oid.h"
+#include "detect-snmp-trap_type.h"
 #include "detect-mqtt-type.h"
 #include "detect-mqtt-flags.h"
 #include "detect-mqtt-qos.h"
@@ -627,6 +630,9 @@ void SigTableSetup(void)
     DetectSNMPVersionRegister();
     DetectSNMPCommunityRegister();
     DetectSNMPPduTypeRegister();
+    DetectSNMPErrorRegister();
+    DetectSNMPOidRegister();
+    DetectSNMPTrapTypeRegister();
     DetectMQTTTypeRegister();
     DetectMQTTFlagsRegister();
     DetectMQTTQosRegister();
@@ -268,6 +268,9 @@ enum DetectKeywordId {
     DETECT_AL_SNMP_VERSION,
     DETECT_AL_SNMP_COMMUNITY,
     DETECT_AL_SNMP_PDU_TYPE,
+    DETECT_AL_SNMP_ERROR,
+    DETECT_AL_SNMP_OID,
+    DETECT_AL_SNMP_TRAP_TYPE,
     DETECT_AL_MQTT_TYPE,
     DETECT_AL_MQTT_FLAGS,
     DETECT_AL_MQTT_QOS,
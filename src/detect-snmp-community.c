@@ -99,8 +99,8 @@ static InspectionBuffer *GetData(DetectEngineThreadCtx *det_ctx,
         uint32_t data_len = 0;
         const uint8_t *data = NULL;
 
-        rs_snmp_tx_get_community(txv, &data, &data_len);
-        if (data == NULL || data_len == 0) {
+        if (!rs_snmp_tx_get_community(txv, &data, &data_len) ||
+                data == NULL || data_len == 0) {
             return NULL;
         }
 
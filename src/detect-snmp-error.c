@@ -0,0 +1,246 @@
+/* Copyright (C) 2015-2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+/**
+ * \file
+ *
+ * \author Pierre Chifflier <chifflier@wzdftpd.net>
+ */
+
+#include "suricata-common.h"
+#include "conf.h"
+#include "detect.h"
+#include "detect-parse.h"
+#include "detect-engine.h"
+#include "detect-engine-content-inspection.h"
+#include "detect-snmp-error.h"
+#include "app-layer-parser.h"
+#include "rust.h"
+
+/**
+ *   [snmp.error]:<status>;
+ *
+ *   <status> is either the numeric value or the name of the error-status
+ *   (for ex. noSuchName).
+ */
+#define PARSE_REGEX "^\\s*([0-9]+|[A-Za-z_]+)\\s*$"
+static DetectParseRegex parse_regex;
+
+typedef struct DetectSNMPErrorData_ {
+    int32_t error;
+} DetectSNMPErrorData;
+
+static DetectSNMPErrorData *DetectSNMPErrorParse (const char *);
+static int DetectSNMPErrorSetup (DetectEngineCtx *, Signature *s, const char *str);
+static void DetectSNMPErrorFree(DetectEngineCtx *, void *);
+#ifdef UNITTESTS
+static void DetectSNMPErrorRegisterTests(void);
+#endif
+static int g_snmp_error_buffer_id = 0;
+
+static int DetectEngineInspectSNMPErrorGeneric(DetectEngineCtx *de_ctx,
+        DetectEngineThreadCtx *det_ctx, const struct DetectEngineAppInspectionEngine_ *engine,
+        const Signature *s, Flow *f, uint8_t flags, void *alstate, void *txv, uint64_t tx_id);
+
+static int DetectSNMPErrorMatch (DetectEngineThreadCtx *, Flow *,
+                                 uint8_t, void *, void *, const Signature *,
+                                 const SigMatchCtx *);
+
+void DetectSNMPErrorRegister(void)
+{
+    sigmatch_table[DETECT_AL_SNMP_ERROR].name = "snmp.error";
+    sigmatch_table[DETECT_AL_SNMP_ERROR].desc = "match SNMP error-status";
+    sigmatch_table[DETECT_AL_SNMP_ERROR].url = "/rules/snmp-keywords.html#snmp-error";
+    sigmatch_table[DETECT_AL_SNMP_ERROR].Match = NULL;
+    sigmatch_table[DETECT_AL_SNMP_ERROR].AppLayerTxMatch = DetectSNMPErrorMatch;
+    sigmatch_table[DETECT_AL_SNMP_ERROR].Setup = DetectSNMPErrorSetup;
+    sigmatch_table[DETECT_AL_SNMP_ERROR].Free = DetectSNMPErrorFree;
+#ifdef UNITTESTS
+    sigmatch_table[DETECT_AL_SNMP_ERROR].RegisterTests = DetectSNMPErrorRegisterTests;
+#endif
+
+    DetectSetupParseRegexes(PARSE_REGEX, &parse_regex);
+
+    DetectAppLayerInspectEngineRegister2("snmp.error", ALPROTO_SNMP, SIG_FLAG_TOSERVER, 0,
+            DetectEngineInspectSNMPErrorGeneric, NULL);
+
+    DetectAppLayerInspectEngineRegister2("snmp.error", ALPROTO_SNMP, SIG_FLAG_TOCLIENT, 0,
+            DetectEngineInspectSNMPErrorGeneric, NULL);
+
+    g_snmp_error_buffer_id = DetectBufferTypeGetByName("snmp.error");
+}
+
+static int DetectEngineInspectSNMPErrorGeneric(DetectEngineCtx *de_ctx,
+        DetectEngineThreadCtx *det_ctx, const struct DetectEngineAppInspectionEngine_ *engine,
+        const Signature *s, Flow *f, uint8_t flags, void *alstate, void *txv, uint64_t tx_id)
+{
+    return DetectEngineInspectGenericList(
+            de_ctx, det_ctx, s, engine->smd, f, flags, alstate, txv, tx_id);
+}
+
+/**
+ * \internal
+ * \brief Function to match error-status of a TX
+ *
+ * \param t       Pointer to thread vars.
+ * \param det_ctx Pointer to the pattern matcher thread.
+ * \param f       Pointer to the current flow.
+ * \param flags   Flags.
+ * \param state   App layer state.
+ * \param s       Pointer to the Signature.
+ * \param m       Pointer to the sigmatch that we will cast into
+ *                DetectSNMPErrorData.
+ *
+ * \retval 0 no match.
+ * \retval 1 match.
+ */
+static int DetectSNMPErrorMatch (DetectEngineThreadCtx *det_ctx,
+                                 Flow *f, uint8_t flags, void *state,
+                                 void *txv, const Signature *s,
+                                 const SigMatchCtx *ctx)
+{
+    SCEnter();
+
+    const DetectSNMPErrorData *dd = (const DetectSNMPErrorData *)ctx;
+    int32_t status;
+    if (rs_snmp_tx_get_error_status(txv, &status)) {
+        SCLogDebug("error %d ref_error %d", status, dd->error);
+        if (status == dd->error)
+            SCReturnInt(1);
+    }
+    SCReturnInt(0);
+}
+
+/**
+ * \internal
+ * \brief Function to parse options passed via snmp.error keywords.
+ *
+ * \param rawstr Pointer to the user provided options.
+ *
+ * \retval dd pointer to DetectSNMPErrorData on success.
+ * \retval NULL on failure.
+ */
+static DetectSNMPErrorData *DetectSNMPErrorParse (const char *rawstr)
+{
+    DetectSNMPErrorData *dd = NULL;
+    int ret = 0, res = 0;
+    int ov[MAX_SUBSTRINGS];
+    char value1[32] = "";
+    char *endptr = NULL;
+
+    ret = DetectParsePcreExec(&parse_regex, rawstr, 0, 0, ov, MAX_SUBSTRINGS);
+    if (ret != 2) {
+        SCLogError(SC_ERR_PCRE_MATCH, "Parse error %s", rawstr);
+        goto error;
+    }
+
+    res = pcre_copy_substring((char *)rawstr, ov, MAX_SUBSTRINGS, 1, value1,
+                              sizeof(value1));
+    if (res < 0) {
+        SCLogError(SC_ERR_PCRE_GET_SUBSTRING, "pcre_copy_substring failed");
+        goto error;
+    }
+
+    dd = SCCalloc(1, sizeof(DetectSNMPErrorData));
+    if (unlikely(dd == NULL))
+        goto error;
+
+    /* set the value */
+    if (isdigit((unsigned char)value1[0])) {
+        unsigned long status = strtoul(value1, &endptr, 10);
+        if (endptr == NULL || *endptr != '\0' || status > INT32_MAX) {
+            SCLogError(SC_ERR_INVALID_SIGNATURE, "invalid character as arg "
+                       "to snmp.error keyword");
+            goto error;
+        }
+        dd->error = (int32_t)status;
+    } else {
+        int status = rs_snmp_cstr_error_status(value1);
+        if (status < 0) {
+            SCLogError(SC_ERR_INVALID_SIGNATURE, "unknown error-status %s "
+                       "as arg to snmp.error keyword", value1);
+            goto error;
+        }
+        dd->error = (int32_t)status;
+    }
+
+    return dd;
+
+error:
+    if (dd)
+        SCFree(dd);
+    return NULL;
+}
+
+/**
+ * \brief Function to add the parsed snmp error-status into the current signature.
+ *
+ * \param de_ctx Pointer to the Detection Engine Context.
+ * \param s      Pointer to the Current Signature.
+ * \param rawstr Pointer to the user provided flags options.
+ * \param type   Defines if this is notBefore or notAfter.
+ *
+ * \retval 0 on Success.
+ * \retval -1 on Failure.
+ */
+static int DetectSNMPErrorSetup (DetectEngineCtx *de_ctx, Signature *s,
+                                 const char *rawstr)
+{
+    DetectSNMPErrorData *dd = NULL;
+    SigMatch *sm = NULL;
+
+    if (DetectSignatureSetAppProto(s, ALPROTO_SNMP) != 0)
+        return -1;
+
+    dd = DetectSNMPErrorParse(rawstr);
+    if (dd == NULL) {
+        SCLogError(SC_ERR_INVALID_ARGUMENT,"Parsing \'%s\' failed", rawstr);
+        goto error;
+    }
+
+    /* okay so far so good, lets get this into a SigMatch
+     * and put it in the Signature. */
+    sm = SigMatchAlloc();
+    if (sm == NULL)
+        goto error;
+
+    sm->type = DETECT_AL_SNMP_ERROR;
+    sm->ctx = (void *)dd;
+
+    SCLogDebug("snmp.error %d", dd->error);
+    SigMatchAppendSMToList(s, sm, g_snmp_error_buffer_id);
+    return 0;
+
+error:
+    DetectSNMPErrorFree(de_ctx, dd);
+    return -1;
+}
+
+/**
+ * \internal
+ * \brief Function to free memory associated with DetectSNMPErrorData.
+ *
+ * \param de_ptr Pointer to DetectSNMPErrorData.
+ */
+static void DetectSNMPErrorFree(DetectEngineCtx *de_ctx, void *ptr)
+{
+    SCFree(ptr);
+}
+
+#ifdef UNITTESTS
+#include "tests/detect-snmp-error.c"
+#endif /* UNITTESTS */
@@ -0,0 +1,227 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+/**
+ * \file
+ *
+ * Set up of the "snmp.oid" keyword to allow content inspections
+ * on the OIDs of the varbinds, in dotted form.
+ */
+
+#include "suricata-common.h"
+#include "conf.h"
+#include "detect.h"
+#include "detect-parse.h"
+#include "detect-engine.h"
+#include "detect-engine-mpm.h"
+#include "detect-engine-prefilter.h"
+#include "detect-engine-content-inspection.h"
+#include "detect-snmp-oid.h"
+#include "app-layer-parser.h"
+#include "rust.h"
+
+static int DetectSNMPOidSetup(DetectEngineCtx *, Signature *,
+    const char *);
+#ifdef UNITTESTS
+static void DetectSNMPOidRegisterTests(void);
+#endif
+static int g_snmp_oid_buffer_id = 0;
+
+struct SNMPOidGetDataArgs {
+    uint32_t local_id; /**< used as index into thread inspect array */
+    void *txv;
+};
+
+static InspectionBuffer *GetSNMPOidData(DetectEngineThreadCtx *det_ctx,
+        const DetectEngineTransforms *transforms,
+        Flow *_f, const struct SNMPOidGetDataArgs *cbdata,
+        int list_id, bool first)
+{
+    SCEnter();
+
+    InspectionBuffer *buffer =
+            InspectionBufferMultipleForListGet(det_ctx, list_id, cbdata->local_id);
+    if (buffer == NULL)
+        return NULL;
+    if (!first && buffer->inspect != NULL)
+        return buffer;
+
+    uint32_t b_len = 0;
+    const uint8_t *b = NULL;
+
+    if (!rs_snmp_tx_get_vars(cbdata->txv, cbdata->local_id, &b, &b_len))
+        return NULL;
+    if (b == NULL || b_len == 0)
+        return NULL;
+
+    InspectionBufferSetupMulti(buffer, transforms, b, b_len);
+
+    SCReturnPtr(buffer, "InspectionBuffer");
+}
+
+static int DetectEngineInspectSNMPOid(
+        DetectEngineCtx *de_ctx, DetectEngineThreadCtx *det_ctx,
+        const DetectEngineAppInspectionEngine *engine,
+        const Signature *s,
+        Flow *f, uint8_t flags, void *alstate, void *txv, uint64_t tx_id)
+{
+    uint32_t local_id = 0;
+
+    const DetectEngineTransforms *transforms = NULL;
+    if (!engine->mpm) {
+        transforms = engine->v2.transforms;
+    }
+
+    while (1) {
+        struct SNMPOidGetDataArgs cbdata = { local_id, txv, };
+        InspectionBuffer *buffer = GetSNMPOidData(det_ctx,
+                transforms, f, &cbdata, engine->sm_list, false);
+
+        if (buffer == NULL || buffer->inspect == NULL)
+            break;
+
+        det_ctx->buffer_offset = 0;
+        det_ctx->discontinue_matching = 0;
+        det_ctx->inspection_recursion_counter = 0;
+
+        const int match = DetectEngineContentInspection(de_ctx, det_ctx, s, engine->smd,
+                                              NULL, f,
+                                              (uint8_t *)buffer->inspect,
+                                              buffer->inspect_len,
+                                              buffer->inspect_offset, DETECT_CI_FLAGS_SINGLE,
+                                              DETECT_ENGINE_CONTENT_INSPECTION_MODE_STATE);
+        if (match == 1) {
+            return DETECT_ENGINE_INSPECT_SIG_MATCH;
+        }
+        local_id++;
+    }
+
+    return DETECT_ENGINE_INSPECT_SIG_NO_MATCH;
+}
+
+typedef struct PrefilterMpmSNMPOid {
+    int list_id;
+    const MpmCtx *mpm_ctx;
+    const DetectEngineTransforms *transforms;
+} PrefilterMpmSNMPOid;
+
+/** \brief SNMPOid Mpm prefilter callback
+ *
+ *  \param det_ctx detection engine thread ctx
+ *  \param p packet to inspect
+ *  \param f flow to inspect
+ *  \param txv tx to inspect
+ *  \param pectx inspection context
+ */
+static void PrefilterTxSNMPOid(DetectEngineThreadCtx *det_ctx,
+        const void *pectx,
+        Packet *p, Flow *f, void *txv,
+        const uint64_t idx, const uint8_t flags)
+{
+    SCEnter();
+
+    const PrefilterMpmSNMPOid *ctx = (const PrefilterMpmSNMPOid *)pectx;
+    const MpmCtx *mpm_ctx = ctx->mpm_ctx;
+    const int list_id = ctx->list_id;
+
+    uint32_t local_id = 0;
+
+    while(1) {
+        // loop until we get a NULL
+
+        struct SNMPOidGetDataArgs cbdata = { local_id, txv };
+        InspectionBuffer *buffer = GetSNMPOidData(det_ctx, ctx->transforms,
+                f, &cbdata, list_id, true);
+        if (buffer == NULL)
+            break;
+
+        if (buffer->inspect_len >= mpm_ctx->minlen) {
+            (void)mpm_table[mpm_ctx->mpm_type].Search(mpm_ctx,
+                    &det_ctx->mtcu, &det_ctx->pmq,
+                    buffer->inspect, buffer->inspect_len);
+        }
+
+        local_id++;
+    }
+}
+
+static void PrefilterMpmSNMPOidFree(void *ptr)
+{
+    SCFree(ptr);
+}
+
+static int PrefilterMpmSNMPOidRegister(DetectEngineCtx *de_ctx,
+        SigGroupHead *sgh, MpmCtx *mpm_ctx,
+        const DetectBufferMpmRegistery *mpm_reg, int list_id)
+{
+    PrefilterMpmSNMPOid *pectx = SCCalloc(1, sizeof(*pectx));
+    if (pectx == NULL)
+        return -1;
+    pectx->list_id = list_id;
+    pectx->mpm_ctx = mpm_ctx;
+    pectx->transforms = &mpm_reg->transforms;
+
+    return PrefilterAppendTxEngine(de_ctx, sgh, PrefilterTxSNMPOid,
+            mpm_reg->app_v2.alproto, mpm_reg->app_v2.tx_min_progress,
+            pectx, PrefilterMpmSNMPOidFree, mpm_reg->name);
+}
+
+void DetectSNMPOidRegister(void)
+{
+    sigmatch_table[DETECT_AL_SNMP_OID].name = "snmp.oid";
+    sigmatch_table[DETECT_AL_SNMP_OID].desc =
+        "SNMP content modifier to match on the OIDs of the varbinds";
+    sigmatch_table[DETECT_AL_SNMP_OID].Setup = DetectSNMPOidSetup;
+#ifdef UNITTESTS
+    sigmatch_table[DETECT_AL_SNMP_OID].RegisterTests = DetectSNMPOidRegisterTests;
+#endif
+    sigmatch_table[DETECT_AL_SNMP_OID].url = "/rules/snmp-keywords.html#snmp-oid";
+
+    sigmatch_table[DETECT_AL_SNMP_OID].flags |= SIGMATCH_NOOPT|SIGMATCH_INFO_STICKY_BUFFER;
+
+    /* register inspect engines */
+    DetectAppLayerInspectEngineRegister2("snmp.oid",
+            ALPROTO_SNMP, SIG_FLAG_TOSERVER, 0,
+            DetectEngineInspectSNMPOid, NULL);
+    DetectAppLayerMpmRegister2("snmp.oid", SIG_FLAG_TOSERVER, 2,
+            PrefilterMpmSNMPOidRegister, NULL, ALPROTO_SNMP, 0);
+    DetectAppLayerInspectEngineRegister2("snmp.oid",
+            ALPROTO_SNMP, SIG_FLAG_TOCLIENT, 0,
+            DetectEngineInspectSNMPOid, NULL);
+    DetectAppLayerMpmRegister2("snmp.oid", SIG_FLAG_TOCLIENT, 2,
+            PrefilterMpmSNMPOidRegister, NULL, ALPROTO_SNMP, 0);
+
+    DetectBufferTypeSetDescriptionByName("snmp.oid", "SNMP varbind OID");
+
+    g_snmp_oid_buffer_id = DetectBufferTypeGetByName("snmp.oid");
+}
+
+static int DetectSNMPOidSetup(DetectEngineCtx *de_ctx, Signature *s,
+    const char *str)
+{
+    if (DetectBufferSetActiveList(s, g_snmp_oid_buffer_id) < 0)
+        return -1;
+
+    if (DetectSignatureSetAppProto(s, ALPROTO_SNMP) != 0)
+        return -1;
+
+    return 0;
+}
+
+#ifdef UNITTESTS
+#include "tests/detect-snmp-oid.c"
+#endif /* UNITTESTS */
@@ -33,8 +33,11 @@
 
 /**
  *   [snmp.pdu_type]:<type>;
+ *
+ *   <type> is either the numeric value or the name of the PDU type
+ *   (for ex. set_request).
  */
-#define PARSE_REGEX "^\\s*([0-9]+)\\s*$"
+#define PARSE_REGEX "^\\s*([0-9]+|[A-Za-z_]+)\\s*$"
 static DetectParseRegex parse_regex;
 
 typedef struct DetectSNMPPduTypeData_ {
@@ -114,11 +117,18 @@ static int DetectSNMPPduTypeMatch (DetectEngineThreadCtx *det_ctx,
 
     const DetectSNMPPduTypeData *dd = (const DetectSNMPPduTypeData *)ctx;
     uint32_t pdu_type;
-    rs_snmp_tx_get_pdu_type(txv, &pdu_type);
-    SCLogDebug("pdu_type %u ref_pdu_type %d",
-            pdu_type, dd->pdu_type);
-    if (pdu_type == dd->pdu_type)
-        SCReturnInt(1);
+    if (rs_snmp_tx_get_pdu_type(txv, &pdu_type)) {
+        SCLogDebug("pdu_type %u ref_pdu_type %d",
+                pdu_type, dd->pdu_type);
+        if (pdu_type == dd->pdu_type)
+            SCReturnInt(1);
+    }
+    if (rs_snmp_tx_get_response_pdu_type(txv, &pdu_type)) {
+        SCLogDebug("response pdu_type %u ref_pdu_type %d",
+                pdu_type, dd->pdu_type);
+        if (pdu_type == dd->pdu_type)
+            SCReturnInt(1);
+    }
     SCReturnInt(0);
 }
 
@@ -157,11 +167,21 @@ static DetectSNMPPduTypeData *DetectSNMPPduTypeParse (const char *rawstr)
         goto error;
 
     /* set the value */
-    dd->pdu_type = strtoul(value1, &endptr, 10);
-    if (endptr == NULL || *endptr != '\0') {
-        SCLogError(SC_ERR_INVALID_SIGNATURE, "invalid character as arg "
-                   "to snmp.pdu_type keyword");
-        goto error;
+    if (isdigit((unsigned char)value1[0])) {
+        dd->pdu_type = strtoul(value1, &endptr, 10);
+        if (endptr == NULL || *endptr != '\0') {
+            SCLogError(SC_ERR_INVALID_SIGNATURE, "invalid character as arg "
+                       "to snmp.pdu_type keyword");
+            goto error;
+        }
+    } else {
+        int pdu_type = rs_snmp_cstr_pdu_type(value1);
+        if (pdu_type < 0) {
+            SCLogError(SC_ERR_INVALID_SIGNATURE, "unknown pdu type %s "
+                       "as arg to snmp.pdu_type keyword", value1);
+            goto error;
+        }
+        dd->pdu_type = (uint32_t)pdu_type;
     }
 
     return dd;
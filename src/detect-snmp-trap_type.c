@@ -0,0 +1,270 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+/**
+ * \file
+ *
+ * \author Pierre Chifflier <chifflier@wzdftpd.net>
+ */
+
+#include "suricata-common.h"
+#include "conf.h"
+#include "detect.h"
+#include "detect-parse.h"
+#include "detect-engine.h"
+#include "detect-engine-content-inspection.h"
+#include "detect-snmp-trap_type.h"
+#include "app-layer-parser.h"
+#include "rust.h"
+
+/**
+ *   [snmp.trap_type]:<generic>[,<specific>];
+ *
+ *   <generic> is the generic-trap code of a SNMPv1 trap (0 to 6), and
+ *   <specific> the specific-trap code of an enterprise specific (6) trap.
+ */
+#define PARSE_REGEX "^\\s*([0-9]+)\\s*(?:,\\s*([0-9]+)\\s*)?$"
+static DetectParseRegex parse_regex;
+
+#define SNMP_TRAP_ENTERPRISE_SPECIFIC 6
+
+typedef struct DetectSNMPTrapTypeData_ {
+    uint32_t generic_trap;
+    bool has_specific_trap;
+    uint32_t specific_trap;
+} DetectSNMPTrapTypeData;
+
+static DetectSNMPTrapTypeData *DetectSNMPTrapTypeParse (const char *);
+static int DetectSNMPTrapTypeSetup (DetectEngineCtx *, Signature *s, const char *str);
+static void DetectSNMPTrapTypeFree(DetectEngineCtx *, void *);
+#ifdef UNITTESTS
+static void DetectSNMPTrapTypeRegisterTests(void);
+#endif
+static int g_snmp_trap_type_buffer_id = 0;
+
+static int DetectEngineInspectSNMPTrapTypeGeneric(DetectEngineCtx *de_ctx,
+        DetectEngineThreadCtx *det_ctx, const struct DetectEngineAppInspectionEngine_ *engine,
+        const Signature *s, Flow *f, uint8_t flags, void *alstate, void *txv, uint64_t tx_id);
+
+static int DetectSNMPTrapTypeMatch (DetectEngineThreadCtx *, Flow *,
+                                    uint8_t, void *, void *, const Signature *,
+                                    const SigMatchCtx *);
+
+void DetectSNMPTrapTypeRegister(void)
+{
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].name = "snmp.trap_type";
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].desc = "match SNMPv1 trap generic and specific codes";
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].url = "/rules/snmp-keywords.html#snmp-trap-type";
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].Match = NULL;
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].AppLayerTxMatch = DetectSNMPTrapTypeMatch;
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].Setup = DetectSNMPTrapTypeSetup;
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].Free = DetectSNMPTrapTypeFree;
+#ifdef UNITTESTS
+    sigmatch_table[DETECT_AL_SNMP_TRAP_TYPE].RegisterTests = DetectSNMPTrapTypeRegisterTests;
+#endif
+
+    DetectSetupParseRegexes(PARSE_REGEX, &parse_regex);
+
+    DetectAppLayerInspectEngineRegister2("snmp.trap_type", ALPROTO_SNMP, SIG_FLAG_TOSERVER, 0,
+            DetectEngineInspectSNMPTrapTypeGeneric, NULL);
+
+    DetectAppLayerInspectEngineRegister2("snmp.trap_type", ALPROTO_SNMP, SIG_FLAG_TOCLIENT, 0,
+            DetectEngineInspectSNMPTrapTypeGeneric, NULL);
+
+    g_snmp_trap_type_buffer_id = DetectBufferTypeGetByName("snmp.trap_type");
+}
+
+static int DetectEngineInspectSNMPTrapTypeGeneric(DetectEngineCtx *de_ctx,
+        DetectEngineThreadCtx *det_ctx, const struct DetectEngineAppInspectionEngine_ *engine,
+        const Signature *s, Flow *f, uint8_t flags, void *alstate, void *txv, uint64_t tx_id)
+{
+    return DetectEngineInspectGenericList(
+            de_ctx, det_ctx, s, engine->smd, f, flags, alstate, txv, tx_id);
+}
+
+/**
+ * \internal
+ * \brief Function to match the trap codes of a TX
+ *
+ * \param t       Pointer to thread vars.
+ * \param det_ctx Pointer to the pattern matcher thread.
+ * \param f       Pointer to the current flow.
+ * \param flags   Flags.
+ * \param state   App layer state.
+ * \param s       Pointer to the Signature.
+ * \param m       Pointer to the sigmatch that we will cast into
+ *                DetectSNMPTrapTypeData.
+ *
+ * \retval 0 no match.
+ * \retval 1 match.
+ */
+static int DetectSNMPTrapTypeMatch (DetectEngineThreadCtx *det_ctx,
+                                    Flow *f, uint8_t flags, void *state,
+                                    void *txv, const Signature *s,
+                                    const SigMatchCtx *ctx)
+{
+    SCEnter();
+
+    const DetectSNMPTrapTypeData *dd = (const DetectSNMPTrapTypeData *)ctx;
+    uint32_t generic_trap, specific_trap;
+    if (!rs_snmp_tx_get_generic_trap(txv, &generic_trap))
+        SCReturnInt(0);
+    SCLogDebug("generic_trap %u ref_generic_trap %u", generic_trap, dd->generic_trap);
+    if (generic_trap != dd->generic_trap)
+        SCReturnInt(0);
+    if (!dd->has_specific_trap)
+        SCReturnInt(1);
+    if (rs_snmp_tx_get_specific_trap(txv, &specific_trap)) {
+        SCLogDebug("specific_trap %u ref_specific_trap %u", specific_trap, dd->specific_trap);
+        if (specific_trap == dd->specific_trap)
+            SCReturnInt(1);
+    }
+    SCReturnInt(0);
+}
+
+/**
+ * \internal
+ * \brief Function to parse options passed via snmp.trap_type keywords.
+ *
+ * \param rawstr Pointer to the user provided options.
+ *
+ * \retval dd pointer to DetectSNMPTrapTypeData on success.
+ * \retval NULL on failure.
+ */
+static DetectSNMPTrapTypeData *DetectSNMPTrapTypeParse (const char *rawstr)
+{
+    DetectSNMPTrapTypeData *dd = NULL;
+    int ret = 0, res = 0;
+    int ov[MAX_SUBSTRINGS];
+    char value1[20] = "";
+    char value2[20] = "";
+    char *endptr = NULL;
+    unsigned long code;
+
+    ret = DetectParsePcreExec(&parse_regex, rawstr, 0, 0, ov, MAX_SUBSTRINGS);
+    if (ret < 2 || ret > 3) {
+        SCLogError(SC_ERR_PCRE_MATCH, "Parse error %s", rawstr);
+        goto error;
+    }
+
+    res = pcre_copy_substring((char *)rawstr, ov, MAX_SUBSTRINGS, 1, value1,
+                              sizeof(value1));
+    if (res < 0) {
+        SCLogError(SC_ERR_PCRE_GET_SUBSTRING, "pcre_copy_substring failed");
+        goto error;
+    }
+
+    dd = SCCalloc(1, sizeof(DetectSNMPTrapTypeData));
+    if (unlikely(dd == NULL))
+        goto error;
+
+    /* set the values */
+    code = strtoul(value1, &endptr, 10);
+    if (endptr == NULL || *endptr != '\0' || code > SNMP_TRAP_ENTERPRISE_SPECIFIC) {
+        SCLogError(SC_ERR_INVALID_SIGNATURE, "invalid generic trap %s "
+                   "as arg to snmp.trap_type keyword", value1);
+        goto error;
+    }
+    dd->generic_trap = (uint32_t)code;
+
+    if (ret == 3) {
+        res = pcre_copy_substring((char *)rawstr, ov, MAX_SUBSTRINGS, 2, value2,
+                                  sizeof(value2));
+        if (res < 0) {
+            SCLogError(SC_ERR_PCRE_GET_SUBSTRING, "pcre_copy_substring failed");
+            goto error;
+        }
+        if (dd->generic_trap != SNMP_TRAP_ENTERPRISE_SPECIFIC) {
+            SCLogError(SC_ERR_INVALID_SIGNATURE, "specific trap is only valid "
+                       "with generic trap 6 in snmp.trap_type keyword");
+            goto error;
+        }
+        code = strtoul(value2, &endptr, 10);
+        if (endptr == NULL || *endptr != '\0' || code > UINT32_MAX) {
+            SCLogError(SC_ERR_INVALID_SIGNATURE, "invalid specific trap %s "
+                       "as arg to snmp.trap_type keyword", value2);
+            goto error;
+        }
+        dd->has_specific_trap = true;
+        dd->specific_trap = (uint32_t)code;
+    }
+
+    return dd;
+
+error:
+    if (dd)
+        SCFree(dd);
+    return NULL;
+}
+
+/**
+ * \brief Function to add the parsed snmp trap codes into the current signature.
+ *
+ * \param de_ctx Pointer to the Detection Engine Context.
+ * \param s      Pointer to the Current Signature.
+ * \param rawstr Pointer to the user provided flags options.
+ *
+ * \retval 0 on Success.
+ * \retval -1 on Failure.
+ */
+static int DetectSNMPTrapTypeSetup (DetectEngineCtx *de_ctx, Signature *s,
+                                    const char *rawstr)
+{
+    DetectSNMPTrapTypeData *dd = NULL;
+    SigMatch *sm = NULL;
+
+    if (DetectSignatureSetAppProto(s, ALPROTO_SNMP) != 0)
+        return -1;
+
+    dd = DetectSNMPTrapTypeParse(rawstr);
+    if (dd == NULL) {
+        SCLogError(SC_ERR_INVALID_ARGUMENT,"Parsing \'%s\' failed", rawstr);
+        goto error;
+    }
+
+    /* okay so far so good, lets get this into a SigMatch
+     * and put it in the Signature. */
+    sm = SigMatchAlloc();
+    if (sm == NULL)
+        goto error;
+
+    sm->type = DETECT_AL_SNMP_TRAP_TYPE;
+    sm->ctx = (void *)dd;
+
+    SCLogDebug("snmp.trap_type %u", dd->generic_trap);
+    SigMatchAppendSMToList(s, sm, g_snmp_trap_type_buffer_id);
+    return 0;
+
+error:
+    DetectSNMPTrapTypeFree(de_ctx, dd);
+    return -1;
+}
+
+/**
+ * \internal
+ * \brief Function to free memory associated with DetectSNMPTrapTypeData.
+ *
+ * \param de_ptr Pointer to DetectSNMPTrapTypeData.
+ */
+static void DetectSNMPTrapTypeFree(DetectEngineCtx *de_ctx, void *ptr)
+{
+    SCFree(ptr);
+}
+
+#ifdef UNITTESTS
+#include "tests/detect-snmp-trap_type.c"
+#endif /* UNITTESTS */
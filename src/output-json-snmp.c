@@ -61,7 +61,7 @@ static int JsonSNMPLogger(ThreadVars *tv, void *thread_data,
     }
 
     jb_open_object(jb, "snmp");
-    if (!rs_snmp_log_json_response(jb, state, snmptx)) {
+    if (!rs_snmp_log_json(snmptx, jb)) {
         goto error;
     }
     jb_close(jb);
@@ -80,6 +80,7 @@ static OutputInitResult OutputSNMPLogInitSub(ConfNode *conf,
     OutputCtx *parent_ctx)
 {
     AppLayerParserRegisterLogger(IPPROTO_UDP, ALPROTO_SNMP);
+    AppLayerParserRegisterLogger(IPPROTO_TCP, ALPROTO_SNMP);
     return OutputJsonLogInitSub(conf, parent_ctx);
 }
 
@@ -104,8 +104,182 @@ static int DetectSNMPCommunityTest(void)
     PASS;
 }
 
+/**
+ * \test Test that a v2c community matches the "private" content.
+ */
+static int DetectSNMPCommunityTest02(void)
+{
+    AppLayerParserThreadCtx *alp_tctx = AppLayerParserThreadCtxAlloc();
+    DetectEngineThreadCtx *det_ctx = NULL;
+    DetectEngineCtx *de_ctx = NULL;
+    Flow f;
+    Packet *p;
+    TcpSession tcp;
+    ThreadVars tv;
+    Signature *s;
+
+    /* SNMPv2c GetRequest, community "private" */
+    uint8_t request[] = {
+        0x30, 0x27, 0x02, 0x01, 0x01, 0x04, 0x07, 0x70,
+        0x72, 0x69, 0x76, 0x61, 0x74, 0x65, 0xa0, 0x19,
+        0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01,
+        0x00, 0x30, 0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b,
+        0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05,
+        0x00
+    };
+
+    /* Setup flow. */
+    memset(&f, 0, sizeof(Flow));
+    memset(&tcp, 0, sizeof(TcpSession));
+    memset(&tv, 0, sizeof(ThreadVars));
+    p = UTHBuildPacket(request, sizeof(request), IPPROTO_UDP);
+    FLOW_INITIALIZE(&f);
+    f.alproto = ALPROTO_SNMP;
+    f.protoctx = (void *)&tcp;
+    f.proto = IPPROTO_UDP;
+    f.protomap = FlowGetProtoMapping(f.proto);
+    f.flags |= FLOW_IPV4;
+    p->flow = &f;
+    p->flags |= PKT_HAS_FLOW | PKT_STREAM_EST;
+    p->flowflags |= FLOW_PKT_TOSERVER | FLOW_PKT_ESTABLISHED;
+    StreamTcpInitConfig(true);
+
+    de_ctx = DetectEngineCtxInit();
+    FAIL_IF_NULL(de_ctx);
+
+    /* This rule should match. */
+    s = DetectEngineAppendSig(de_ctx,
+        "alert snmp any any -> any any ("
+        "msg:\"SNMP Test Rule\"; "
+        "snmp.community; content:\"private\"; "
+        "sid:1; rev:1;)");
+    FAIL_IF_NULL(s);
+
+    /* This rule should not match. */
+    s = DetectEngineAppendSig(de_ctx,
+        "alert snmp any any -> any any ("
+        "msg:\"SNMP Test Rule\"; "
+        "snmp.community; content:\"public\"; "
+        "sid:2; rev:1;)");
+    FAIL_IF_NULL(s);
+
+    SigGroupBuild(de_ctx);
+    DetectEngineThreadCtxInit(&tv, (void *)de_ctx, (void *)&det_ctx);
+
+    int r = AppLayerParserParse(NULL, alp_tctx, &f, ALPROTO_SNMP,
+                        STREAM_TOSERVER, request, sizeof(request));
+    FAIL_IF(r != 0);
+
+    /* Check that we have app-layer state. */
+    FAIL_IF_NULL(f.alstate);
+
+    SigMatchSignatures(&tv, de_ctx, det_ctx, p);
+    FAIL_IF(!PacketAlertCheck(p, 1));
+    FAIL_IF(PacketAlertCheck(p, 2));
+
+    /* Cleanup. */
+    AppLayerParserThreadCtxFree(alp_tctx);
+    DetectEngineThreadCtxDeinit(&tv, det_ctx);
+    SigGroupCleanup(de_ctx);
+    DetectEngineCtxFree(de_ctx);
+    StreamTcpFreeConfig(true);
+    FLOW_DESTROY(&f);
+    UTHFreePacket(p);
+
+    PASS;
+}
+
+/**
+ * \test Test that a v3 message, which has no community, does not match
+ *       even if the user name is "private".
+ */
+static int DetectSNMPCommunityTest03(void)
+{
+    AppLayerParserThreadCtx *alp_tctx = AppLayerParserThreadCtxAlloc();
+    DetectEngineThreadCtx *det_ctx = NULL;
+    DetectEngineCtx *de_ctx = NULL;
+    Flow f;
+    Packet *p;
+    TcpSession tcp;
+    ThreadVars tv;
+    Signature *s;
+
+    /* SNMPv3 GetRequest, noAuthNoPriv, user "private" */
+    uint8_t request[] = {
+        0x30, 0x69, 0x02, 0x01, 0x03, 0x30, 0x0f, 0x02,
+        0x02, 0x12, 0x36, 0x02, 0x03, 0x00, 0xff, 0xe3,
+        0x04, 0x01, 0x04, 0x02, 0x01, 0x03, 0x04, 0x25,
+        0x30, 0x23, 0x04, 0x0d, 0x80, 0x00, 0x1f, 0x88,
+        0x80, 0x59, 0xdc, 0x48, 0x61, 0x45, 0xa2, 0x63,
+        0x22, 0x02, 0x01, 0x01, 0x02, 0x02, 0x01, 0x00,
+        0x04, 0x07, 0x70, 0x72, 0x69, 0x76, 0x61, 0x74,
+        0x65, 0x04, 0x00, 0x04, 0x00, 0x30, 0x2c, 0x04,
+        0x0d, 0x80, 0x00, 0x1f, 0x88, 0x80, 0x59, 0xdc,
+        0x48, 0x61, 0x45, 0xa2, 0x63, 0x22, 0x04, 0x00,
+        0xa0, 0x19, 0x02, 0x01, 0x02, 0x02, 0x01, 0x00,
+        0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c, 0x06,
+        0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01,
+        0x00, 0x05, 0x00
+    };
+
+    /* Setup flow. */
+    memset(&f, 0, sizeof(Flow));
+    memset(&tcp, 0, sizeof(TcpSession));
+    memset(&tv, 0, sizeof(ThreadVars));
+    p = UTHBuildPacket(request, sizeof(request), IPPROTO_UDP);
+    FLOW_INITIALIZE(&f);
+    f.alproto = ALPROTO_SNMP;
+    f.protoctx = (void *)&tcp;
+    f.proto = IPPROTO_UDP;
+    f.protomap = FlowGetProtoMapping(f.proto);
+    f.flags |= FLOW_IPV4;
+    p->flow = &f;
+    p->flags |= PKT_HAS_FLOW | PKT_STREAM_EST;
+    p->flowflags |= FLOW_PKT_TOSERVER | FLOW_PKT_ESTABLISHED;
+    StreamTcpInitConfig(true);
+
+    de_ctx = DetectEngineCtxInit();
+    FAIL_IF_NULL(de_ctx);
+
+    /* This rule should not match. */
+    s = DetectEngineAppendSig(de_ctx,
+        "alert snmp any any -> any any ("
+        "msg:\"SNMP Test Rule\"; "
+        "snmp.community; content:\"private\"; "
+        "sid:1; rev:1;)");
+    FAIL_IF_NULL(s);
+
+    SigGroupBuild(de_ctx);
+    DetectEngineThreadCtxInit(&tv, (void *)de_ctx, (void *)&det_ctx);
+
+    int r = AppLayerParserParse(NULL, alp_tctx, &f, ALPROTO_SNMP,
+                        STREAM_TOSERVER, request, sizeof(request));
+    FAIL_IF(r != 0);
+
+    /* Check that we have app-layer state. */
+    FAIL_IF_NULL(f.alstate);
+
+    SigMatchSignatures(&tv, de_ctx, det_ctx, p);
+    FAIL_IF(PacketAlertCheck(p, 1));
+
+    /* Cleanup. */
+    AppLayerParserThreadCtxFree(alp_tctx);
+    DetectEngineThreadCtxDeinit(&tv, det_ctx);
+    SigGroupCleanup(de_ctx);
+    DetectEngineCtxFree(de_ctx);
+    StreamTcpFreeConfig(true);
+    FLOW_DESTROY(&f);
+    UTHFreePacket(p);
+
+    PASS;
+}
+
 static void DetectSNMPCommunityRegisterTests(void)
 {
     UtRegisterTest("DetectSNMPCommunityTest",
         DetectSNMPCommunityTest);
+    UtRegisterTest("DetectSNMPCommunityTest02",
+        DetectSNMPCommunityTest02);
+    UtRegisterTest("DetectSNMPCommunityTest03",
+        DetectSNMPCommunityTest03);
 }
@@ -0,0 +1,63 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+#include "util-unittest.h"
+#include "util-unittest-helper.h"
+
+/**
+ * \test This is a test for a valid value 16.
+ *
+ * \retval 1 on success.
+ * \retval 0 on failure.
+ */
+static int SNMPErrorTestParse01 (void)
+{
+    DetectSNMPErrorData *dd = NULL;
+    dd = DetectSNMPErrorParse("16");
+    FAIL_IF_NULL(dd);
+    FAIL_IF_NOT(dd->error == 16);
+    DetectSNMPErrorFree(NULL, dd);
+    PASS;
+}
+
+/**
+ * \test This is a test for a valid symbolic value.
+ *
+ * \retval 1 on success.
+ * \retval 0 on failure.
+ */
+static int SNMPErrorTestParse02 (void)
+{
+    DetectSNMPErrorData *dd = NULL;
+    dd = DetectSNMPErrorParse("authorizationError");
+    FAIL_IF_NULL(dd);
+    FAIL_IF_NOT(dd->error == 16);
+    DetectSNMPErrorFree(NULL, dd);
+    dd = DetectSNMPErrorParse("nosuchname");
+    FAIL_IF_NULL(dd);
+    FAIL_IF_NOT(dd->error == 2);
+    DetectSNMPErrorFree(NULL, dd);
+    dd = DetectSNMPErrorParse("no_such_name");
+    FAIL_IF_NOT_NULL(dd);
+    PASS;
+}
+
+static void DetectSNMPErrorRegisterTests(void)
+{
+    UtRegisterTest("SNMPErrorTestParse01", SNMPErrorTestParse01);
+    UtRegisterTest("SNMPErrorTestParse02", SNMPErrorTestParse02);
+}
@@ -34,7 +34,26 @@ static int SNMPValidityTestParse01 (void)
     PASS;
 }
 
+/**
+ * \test This is a test for a valid symbolic value.
+ *
+ * \retval 1 on success.
+ * \retval 0 on failure.
+ */
+static int SNMPValidityTestParse02 (void)
+{
+    DetectSNMPPduTypeData *dd = NULL;
+    dd = DetectSNMPPduTypeParse("get_bulk_request");
+    FAIL_IF_NULL(dd);
+    FAIL_IF_NOT(dd->pdu_type == 5);
+    DetectSNMPPduTypeFree(NULL, dd);
+    dd = DetectSNMPPduTypeParse("getbulk");
+    FAIL_IF_NOT_NULL(dd);
+    PASS;
+}
+
 static void DetectSNMPPduTypeRegisterTests(void)
 {
     UtRegisterTest("SNMPValidityTestParse01", SNMPValidityTestParse01);
+    UtRegisterTest("SNMPValidityTestParse02", SNMPValidityTestParse02);
 }
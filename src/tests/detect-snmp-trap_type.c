@@ -0,0 +1,66 @@
+/* Copyright (C) 2021 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+#include "util-unittest.h"
+#include "util-unittest-helper.h"
+
+/**
+ * \test This is a test for a generic trap code.
+ *
+ * \retval 1 on success.
+ * \retval 0 on failure.
+ */
+static int SNMPTrapTypeTestParse01 (void)
+{
+    DetectSNMPTrapTypeData *dd = NULL;
+    dd = DetectSNMPTrapTypeParse("2");
+    FAIL_IF_NULL(dd);
+    FAIL_IF_NOT(dd->generic_trap == 2);
+    FAIL_IF(dd->has_specific_trap);
+    DetectSNMPTrapTypeFree(NULL, dd);
+    dd = DetectSNMPTrapTypeParse("7");
+    FAIL_IF_NOT_NULL(dd);
+    PASS;
+}
+
+/**
+ * \test This is a test for an enterprise specific trap code.
+ *
+ * \retval 1 on success.
+ * \retval 0 on failure.
+ */
+static int SNMPTrapTypeTestParse02 (void)
+{
+    DetectSNMPTrapTypeData *dd = NULL;
+    dd = DetectSNMPTrapTypeParse("6, 17");
+    FAIL_IF_NULL(dd);
+    FAIL_IF_NOT(dd->generic_trap == 6);
+    FAIL_IF_NOT(dd->has_specific_trap);
+    FAIL_IF_NOT(dd->specific_trap == 17);
+    DetectSNMPTrapTypeFree(NULL, dd);
+    dd = DetectSNMPTrapTypeParse("2,17");
+    FAIL_IF_NOT_NULL(dd);
+    dd = DetectSNMPTrapTypeParse("6,");
+    FAIL_IF_NOT_NULL(dd);
+    PASS;
+}
+
+static void DetectSNMPTrapTypeRegisterTests(void)
+{
+    UtRegisterTest("SNMPTrapTypeTestParse01", SNMPTrapTypeTestParse01);
+    UtRegisterTest("SNMPTrapTypeTestParse02", SNMPTrapTypeTestParse02);
+}